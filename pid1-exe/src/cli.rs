@@ -1,19 +1,28 @@
+#[cfg(target_family = "unix")]
+use crate::config::Config;
+#[cfg(target_family = "unix")]
+use crate::metrics::{self, MetricsAddress};
 use clap::Parser;
 #[cfg(target_family = "unix")]
-use pid1::Pid1Settings;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches};
 #[cfg(target_family = "unix")]
-use signal_hook::{
-    consts::{SIGCHLD, SIGINT, SIGTERM},
-    iterator::Signals,
+use pid1::{
+    ChildStatus, HealthCheck, HealthProbe, MainExitPolicy, Metrics, Pid1Settings, Restart,
+    RestartPolicy, Service, ShutdownPolicy, UnhealthyAction,
 };
 #[cfg(target_family = "unix")]
 use std::os::unix::process::CommandExt;
-#[cfg(target_family = "unix")]
-use std::time::Duration;
 use std::{error::Error, ffi::OsString, path::PathBuf};
+#[cfg(target_family = "unix")]
+use std::{sync::Arc, time::Duration};
 
 #[derive(Parser, Debug, PartialEq)]
 pub(crate) struct Pid1App {
+    /// Load settings from a TOML file. Options given on the command line
+    /// take precedence.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "FILE")]
+    pub(crate) config: Option<PathBuf>,
     /// Specify working direcory
     #[arg(short, long, value_name = "DIR")]
     pub(crate) workdir: Option<PathBuf>,
@@ -32,54 +41,484 @@ pub(crate) struct Pid1App {
     /// Run command with group ID
     #[arg(short, long, value_name = "GROUP_ID")]
     group_id: Option<u32>,
+    /// Signal to forward to the child process (e.g. HUP, SIGUSR1,
+    /// RTMIN+3 or 10). Can specify multiple times. Defaults to all
+    /// catchable signals.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "SIGNAL", value_parser = parse_signal)]
+    pub(crate) forward_signal: Vec<i32>,
+    /// Rewrite a signal before delivering it to the child process
+    /// (e.g. TERM=QUIT). Use IGNORE as the target to drop the signal.
+    /// Can specify multiple times.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_signal_rewrite)]
+    pub(crate) rewrite_signal: Vec<(i32, Option<i32>)>,
+    /// Run the process in its own process group and deliver signals to
+    /// the whole group
+    #[arg(long, default_value_t = false)]
+    pub(crate) process_group: bool,
+    /// Run the process in its own process group in the foreground of
+    /// the terminal, for interactive use (e.g. docker run -it)
+    #[arg(long, default_value_t = false)]
+    pub(crate) tty: bool,
+    /// Supervise the process and reap orphans even when not running as
+    /// PID 1, by becoming a child subreaper (Linux only)
+    #[arg(long, default_value_t = false)]
+    pub(crate) subreaper: bool,
+    /// Steps to stop the process on SIGTERM/SIGINT, as a comma separated
+    /// list of SIGNAL[:WAIT] (e.g. TERM:10s,INT:5s,KILL). RECEIVED
+    /// stands for the received signal. Overrides --timeout.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "STEPS")]
+    pub(crate) stop_sequence: Option<ShutdownPolicy>,
+    /// What to do with remaining processes once the process exits:
+    /// `exit` right away, or `terminate[:WAIT]` them with SIGTERM and
+    /// reap them for up to WAIT before sending SIGKILL
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "POLICY", default_value = "exit")]
+    pub(crate) on_main_exit: MainExitPolicy,
+    /// When to restart the process after it exits: `never`,
+    /// `on-failure` or `always`
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "POLICY", default_value = "never")]
+    pub(crate) restart: Restart,
+    /// Delay before restarting the process, doubled for every restart
+    /// within --restart-window
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "DURATION", default_value = "1s", value_parser = parse_duration)]
+    pub(crate) restart_delay: Duration,
+    /// Maximum delay before restarting the process
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "DURATION", default_value = "30s", value_parser = parse_duration)]
+    pub(crate) restart_max_delay: Duration,
+    /// Give up and exit once the process was restarted COUNT times
+    /// within --restart-window
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "COUNT")]
+    pub(crate) max_restarts: Option<u32>,
+    /// Time after which a restart is forgotten
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "DURATION", default_value = "60s", value_parser = parse_duration)]
+    pub(crate) restart_window: Duration,
+    /// Periodically check the health of the process with a probe:
+    /// `exec:COMMAND`, `tcp:[HOST:]PORT` or `http:[HOST:]PORT[/PATH]`
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "PROBE")]
+    pub(crate) health_check: Option<HealthProbe>,
+    /// Time between two health checks
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "DURATION", default_value = "10s", value_parser = parse_duration)]
+    pub(crate) health_interval: Duration,
+    /// Time after which a health check which hasn't completed fails
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "DURATION", default_value = "5s", value_parser = parse_duration)]
+    pub(crate) health_timeout: Duration,
+    /// Number of failed health checks in a row after which the process
+    /// is unhealthy
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "COUNT", default_value_t = 3)]
+    pub(crate) health_retries: u32,
+    /// What to do once the process is unhealthy: `log`, `restart` it or
+    /// `shutdown`
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "ACTION", default_value = "log")]
+    pub(crate) on_unhealthy: UnhealthyAction,
+    /// Exit with TO instead of FROM once the process has exited (e.g.
+    /// 143=0). Can specify multiple times.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_key_val::<i32, i32>)]
+    pub(crate) map_exit: Vec<(i32, i32)>,
+    /// Comma separated exit codes of the process to exit with 0 instead
+    /// (e.g. 0,143)
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "CODES", value_delimiter = ',')]
+    pub(crate) success_exit_codes: Vec<i32>,
+    /// Serve metrics in the Prometheus text format on ADDRESS:
+    /// `[IP:]PORT` (all interfaces by default) or `unix:PATH`
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "ADDRESS")]
+    pub(crate) metrics: Option<MetricsAddress>,
+    /// Listen for commands on a Unix socket at PATH: status,
+    /// signal NAME, restart, stop or ps
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "PATH")]
+    pub(crate) control_socket: Option<PathBuf>,
+    /// Additional service to supervise along with the process, as
+    /// NAME=COMMAND. The command is split on whitespace, honoring
+    /// quotes. Can specify multiple times.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "NAME=COMMAND", value_parser = parse_service)]
+    pub(crate) service: Vec<(String, Vec<String>)>,
+    /// Override an environment variable of a service, as
+    /// NAME:KEY=VALUE. Can specify multiple times.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "NAME:KEY=VALUE", value_parser = parse_service_option::<(OsString, OsString)>)]
+    pub(crate) service_env: Vec<(String, (OsString, OsString))>,
+    /// Working directory of a service, as NAME:DIR
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "NAME:DIR", value_parser = parse_service_option::<PathBuf>)]
+    pub(crate) service_workdir: Vec<(String, PathBuf)>,
+    /// Run a service with user ID, as NAME:USER_ID
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "NAME:USER_ID", value_parser = parse_service_option::<u32>)]
+    pub(crate) service_user_id: Vec<(String, u32)>,
+    /// Run a service with group ID, as NAME:GROUP_ID
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "NAME:GROUP_ID", value_parser = parse_service_option::<u32>)]
+    pub(crate) service_group_id: Vec<(String, u32)>,
+    /// Service whose exit doesn't stop the other ones. Use `main` for
+    /// the process. Can specify multiple times.
+    #[cfg(target_family = "unix")]
+    #[arg(long, value_name = "NAME")]
+    pub(crate) service_optional: Vec<String>,
     /// Process to run
-    #[arg(required = true)]
-    pub(crate) command: String,
+    #[cfg_attr(
+        target_family = "unix",
+        arg(required_unless_present_any = ["service", "config"])
+    )]
+    #[cfg_attr(target_family = "windows", arg(required = true))]
+    pub(crate) command: Option<String>,
     /// Arguments to the process
     #[arg(required = false)]
     pub(crate) args: Vec<String>,
 }
 
 impl Pid1App {
+    /// Parse the command line, along with the `--config` file if any.
     #[cfg(target_family = "unix")]
-    pub(crate) fn run(self) -> ! {
-        let mut child = std::process::Command::new(&self.command);
-        let child = child.args(&self.args[..]);
-        if let Some(workdir) = &self.workdir {
-            child.current_dir(workdir);
+    pub(crate) fn load() -> Self {
+        let matches = Self::command().get_matches();
+        let mut app = Self::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+        if let Some(path) = &app.config {
+            match Config::load(path) {
+                Ok(config) => app.apply_config(config, &matches),
+                Err(err) => {
+                    eprintln!("pid1: {err}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        app
+    }
+
+    #[cfg(target_family = "windows")]
+    pub(crate) fn load() -> Self {
+        Self::parse()
+    }
+
+    /// Fill in the settings which were not given on the command line
+    /// from `config`. Lists, such as the environment or the services,
+    /// are merged.
+    #[cfg(target_family = "unix")]
+    pub(crate) fn apply_config(&mut self, config: Config, matches: &ArgMatches) {
+        let from_cli = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if self.command.is_none() {
+            self.command = config.command;
+            self.args = config.args;
+        }
+        if !from_cli("workdir") && config.workdir.is_some() {
+            self.workdir = config.workdir;
+        }
+        if let (false, Some(timeout)) = (from_cli("timeout"), config.timeout) {
+            self.timeout = timeout;
+        }
+        if let (false, Some(verbose)) = (from_cli("verbose"), config.verbose) {
+            self.verbose = verbose;
+        }
+        let env = config
+            .env
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()));
+        self.env = env.chain(self.env.drain(..)).collect();
+        if !from_cli("user_id") && config.user_id.is_some() {
+            self.user_id = config.user_id;
+        }
+        if !from_cli("group_id") && config.group_id.is_some() {
+            self.group_id = config.group_id;
+        }
+        if self.forward_signal.is_empty() {
+            self.forward_signal = config
+                .forward_signals
+                .into_iter()
+                .map(|signal| signal.0 .0)
+                .collect();
+        }
+        let rewrites = config
+            .rewrite_signals
+            .into_iter()
+            .map(|(from, to)| (from.0 .0, to.0 .0));
+        self.rewrite_signal = rewrites.chain(self.rewrite_signal.drain(..)).collect();
+        if let (false, Some(process_group)) = (from_cli("process_group"), config.process_group) {
+            self.process_group = process_group;
+        }
+        if let (false, Some(tty)) = (from_cli("tty"), config.tty) {
+            self.tty = tty;
+        }
+        if let (false, Some(subreaper)) = (from_cli("subreaper"), config.subreaper) {
+            self.subreaper = subreaper;
+        }
+        if !from_cli("stop_sequence") {
+            if let Some(policy) = config.stop_sequence {
+                self.stop_sequence = Some(policy.0);
+            }
+        }
+        if let (false, Some(policy)) = (from_cli("on_main_exit"), config.on_main_exit) {
+            self.on_main_exit = policy.0;
+        }
+        let restart = config.restart;
+        if let (false, Some(policy)) = (from_cli("restart"), restart.policy) {
+            self.restart = policy.0;
         }
-        if let Some(user_id) = &self.user_id {
-            child.uid(*user_id);
+        if let (false, Some(delay)) = (from_cli("restart_delay"), restart.delay) {
+            self.restart_delay = delay.0 .0;
         }
-        if let Some(group_id) = &self.group_id {
-            child.gid(*group_id);
+        if let (false, Some(delay)) = (from_cli("restart_max_delay"), restart.max_delay) {
+            self.restart_max_delay = delay.0 .0;
         }
-        for (key, value) in &self.env {
-            child.env(key, value);
+        if !from_cli("max_restarts") && restart.max_restarts.is_some() {
+            self.max_restarts = restart.max_restarts;
         }
+        if let (false, Some(window)) = (from_cli("restart_window"), restart.window) {
+            self.restart_window = window.0 .0;
+        }
+        let health = config.health_check;
+        if !from_cli("health_check") {
+            if let Some(probe) = health.probe {
+                self.health_check = Some(probe.0);
+            }
+        }
+        if let (false, Some(interval)) = (from_cli("health_interval"), health.interval) {
+            self.health_interval = interval.0 .0;
+        }
+        if let (false, Some(timeout)) = (from_cli("health_timeout"), health.timeout) {
+            self.health_timeout = timeout.0 .0;
+        }
+        if let (false, Some(retries)) = (from_cli("health_retries"), health.retries) {
+            self.health_retries = retries;
+        }
+        if let (false, Some(action)) = (from_cli("on_unhealthy"), health.action) {
+            self.on_unhealthy = action.0;
+        }
+        let map_exit = config.map_exit.into_iter().map(|(from, to)| (from.0, to));
+        self.map_exit = map_exit.chain(self.map_exit.drain(..)).collect();
+        if self.success_exit_codes.is_empty() {
+            self.success_exit_codes = config.success_exit_codes;
+        }
+        if let (false, Some(address)) = (from_cli("metrics"), config.metrics) {
+            self.metrics = Some(address.0);
+        }
+        if !from_cli("control_socket") && config.control_socket.is_some() {
+            self.control_socket = config.control_socket;
+        }
+        // Services from the file come first, the command-line options
+        // apply on top of them
+        let mut services = Vec::new();
+        for (name, service) in config.services {
+            let argv = std::iter::once(service.command).chain(service.args);
+            services.push((name.clone(), argv.collect()));
+            let env = service.env.into_iter();
+            let env = env.map(|(key, value)| (name.clone(), (key.into(), value.into())));
+            self.service_env.splice(0..0, env);
+            if let Some(workdir) = service.workdir {
+                self.service_workdir.insert(0, (name.clone(), workdir));
+            }
+            if let Some(user_id) = service.user_id {
+                self.service_user_id.insert(0, (name.clone(), user_id));
+            }
+            if let Some(group_id) = service.group_id {
+                self.service_group_id.insert(0, (name.clone(), group_id));
+            }
+            if !service.critical {
+                self.service_optional.push(name);
+            }
+        }
+        self.service.splice(0..0, services);
+    }
+
+    #[cfg(target_family = "unix")]
+    pub(crate) fn run(self) -> ! {
+        let mut services = match self.services() {
+            Ok(services) => services,
+            Err(err) => {
+                eprintln!("pid1: {err}");
+                std::process::exit(2);
+            }
+        };
         let pid = std::process::id();
-        if pid != 1 {
-            let status = child.exec();
+        if pid != 1 && self.subreaper {
+            if let Err(err) = pid1::become_child_subreaper() {
+                eprintln!("pid1: {err}");
+                std::process::exit(err.exit_code());
+            }
+        }
+        // Running several services requires staying around to
+        // supervise them too
+        if pid != 1 && !self.needs_supervision() && services.len() == 1 {
+            let status = services[0].command_mut().exec();
             eprintln!("execvp failed with: {status:?}");
 
             std::process::exit(1);
         } else {
+            let mut settings = Pid1Settings::new();
+            settings
+                .enable_log(self.verbose)
+                .timeout(Duration::from_secs(self.timeout.into()))
+                .process_group(self.process_group)
+                .tty(self.tty)
+                .on_main_exit(self.on_main_exit);
+            if !self.forward_signal.is_empty() {
+                settings.forward_signals(self.forward_signal.iter().copied());
+            }
+            if let Some(policy) = &self.stop_sequence {
+                settings.shutdown_policy(policy.clone());
+            }
+            let mut restart_policy = RestartPolicy::new(self.restart)
+                .backoff(self.restart_delay, self.restart_max_delay)
+                .window(self.restart_window);
+            if let Some(count) = self.max_restarts {
+                restart_policy = restart_policy.max_restarts(count, self.restart_window);
+            }
+            settings.restart_policy(restart_policy);
+            if let (Some(check), true) = (self.main_health_check(), self.service.is_empty()) {
+                settings.health_check(check);
+            }
+            for (from, to) in &self.rewrite_signal {
+                settings.rewrite_signal(*from, *to);
+            }
+            settings.success_exit_codes(self.success_exit_codes.iter().copied());
+            for (from, to) in &self.map_exit {
+                settings.map_exit_code(*from, *to);
+            }
+            if let Some(address) = &self.metrics {
+                let metrics = Arc::new(Metrics::new());
+                settings.observer(metrics.clone());
+                if let Err(err) = metrics::serve(address, metrics) {
+                    eprintln!("pid1: failed to serve the metrics: {err}");
+                    std::process::exit(1);
+                }
+            }
+            if let Some(path) = &self.control_socket {
+                settings.control_socket(path);
+            }
             // Install signal handlers before launching child process
-            let signals = Signals::new([SIGTERM, SIGINT, SIGCHLD]).unwrap();
-            let child = child.spawn();
-            let child = match child {
-                Ok(child) => child,
+            let signals = match settings.register_signals() {
+                Ok(signals) => signals,
                 Err(err) => {
-                    eprintln!("pid1: {} spawn failed. Got error: {err}", self.command);
-                    std::process::exit(1);
+                    eprintln!("pid1: {err}");
+                    std::process::exit(err.exit_code());
                 }
             };
+            let outcome = if self.service.is_empty() {
+                settings.supervise_command(signals, services[0].command_mut())
+            } else {
+                settings.supervise_services(signals, &mut services)
+            };
+            if let ChildStatus::SpawnFailed(err) = &outcome.status {
+                match &self.command {
+                    Some(command) if self.service.is_empty() => {
+                        eprintln!("pid1: {command} spawn failed. Got error: {err}")
+                    }
+                    _ => eprintln!("pid1: spawn failed. Got error: {err}"),
+                }
+            }
+            std::process::exit(outcome.exit_code())
+        }
+    }
 
-            Pid1Settings::new()
-                .enable_log(self.verbose)
-                .timeout(Duration::from_secs(self.timeout.into()))
-                .pid1_handling(signals, child)
+    /// Whether the options require staying around to supervise the
+    /// process when not running as PID 1, rather than exec it:
+    /// restarting, health checks, mapping the exit code, serving
+    /// metrics, the control socket or handling the signals.
+    #[cfg(target_family = "unix")]
+    fn needs_supervision(&self) -> bool {
+        self.subreaper
+            || self.restart != Restart::Never
+            || self.health_check.is_some()
+            || !self.map_exit.is_empty()
+            || !self.success_exit_codes.is_empty()
+            || self.metrics.is_some()
+            || self.control_socket.is_some()
+            || !self.forward_signal.is_empty()
+            || !self.rewrite_signal.is_empty()
+            || self.stop_sequence.is_some()
+            || self.process_group
+    }
+
+    /// The process to run, named `main`, followed by the additional
+    /// services.
+    #[cfg(target_family = "unix")]
+    fn services(&self) -> Result<Vec<Service>, String> {
+        let mut services = Vec::new();
+        if self.command.is_none() && self.service.is_empty() {
+            return Err("no command to run".to_owned());
+        }
+        if let Some(command) = &self.command {
+            let mut child = std::process::Command::new(command);
+            child.args(&self.args[..]);
+            if let Some(workdir) = &self.workdir {
+                child.current_dir(workdir);
+            }
+            if let Some(user_id) = &self.user_id {
+                child.uid(*user_id);
+            }
+            if let Some(group_id) = &self.group_id {
+                child.gid(*group_id);
+            }
+            for (key, value) in &self.env {
+                child.env(key, value);
+            }
+            services.push(Service::new("main", child));
+        }
+        for (name, argv) in &self.service {
+            if services.iter().any(|service| service.name() == name) {
+                return Err(format!("duplicate service `{name}`"));
+            }
+            let mut command = std::process::Command::new(&argv[0]);
+            command.args(&argv[1..]);
+            services.push(Service::new(name, command));
+        }
+        for (name, (key, value)) in &self.service_env {
+            find_service(&mut services, name)?.env(key, value);
+        }
+        for (name, workdir) in &self.service_workdir {
+            find_service(&mut services, name)?.current_dir(workdir);
         }
+        for (name, user_id) in &self.service_user_id {
+            find_service(&mut services, name)?.uid(*user_id);
+        }
+        for (name, group_id) in &self.service_group_id {
+            find_service(&mut services, name)?.gid(*group_id);
+        }
+        for name in &self.service_optional {
+            find_service(&mut services, name)?;
+        }
+        Ok(services
+            .into_iter()
+            .map(|service| {
+                let critical = !self
+                    .service_optional
+                    .iter()
+                    .any(|name| name == service.name());
+                let service = service.critical(critical);
+                match self.main_health_check() {
+                    Some(check) if service.name() == "main" => service.health_check(check),
+                    _ => service,
+                }
+            })
+            .collect())
+    }
+
+    /// The health check of the process, if any.
+    #[cfg(target_family = "unix")]
+    fn main_health_check(&self) -> Option<HealthCheck> {
+        let probe = self.health_check.clone()?;
+        Some(
+            HealthCheck::new(probe)
+                .interval(self.health_interval)
+                .timeout(self.health_timeout)
+                .failure_threshold(self.health_retries)
+                .action(self.on_unhealthy),
+        )
     }
 
     #[cfg(target_family = "windows")]
@@ -102,3 +541,136 @@ where
         .ok_or_else(|| format!("invalid KEY=value: no `=` found in `{s}`"))?;
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
+
+/// The command of the service named `name`
+#[cfg(target_family = "unix")]
+fn find_service<'a>(
+    services: &'a mut [Service],
+    name: &str,
+) -> Result<&'a mut std::process::Command, String> {
+    services
+        .iter_mut()
+        .find(|service| service.name() == name)
+        .map(Service::command_mut)
+        .ok_or_else(|| format!("unknown service `{name}`"))
+}
+
+/// Parse a NAME=COMMAND service, splitting the command like a shell
+/// would
+#[cfg(target_family = "unix")]
+fn parse_service(s: &str) -> Result<(String, Vec<String>), String> {
+    let (name, command) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid NAME=COMMAND: no `=` found in `{s}`"))?;
+    let argv =
+        pid1::split_command(command).ok_or_else(|| format!("unbalanced quotes in `{command}`"))?;
+    if name.is_empty() || argv.is_empty() {
+        return Err(format!("invalid NAME=COMMAND: `{s}`"));
+    }
+    Ok((name.to_owned(), argv))
+}
+
+/// Parse a NAME:VALUE service option
+#[cfg(target_family = "unix")]
+fn parse_service_option<T>(s: &str) -> Result<(String, T), Box<dyn Error + Send + Sync + 'static>>
+where
+    T: ServiceOption,
+{
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid NAME:VALUE: no `:` found in `{s}`"))?;
+    Ok((name.to_owned(), T::parse(value)?))
+}
+
+/// Value of a service option
+#[cfg(target_family = "unix")]
+trait ServiceOption: Sized {
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync + 'static>>;
+}
+
+#[cfg(target_family = "unix")]
+impl ServiceOption for (OsString, OsString) {
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        parse_key_val(s)
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl ServiceOption for PathBuf {
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(s.into())
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl ServiceOption for u32 {
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(s.parse()?)
+    }
+}
+
+/// Parse a signal name or number
+#[cfg(target_family = "unix")]
+fn parse_signal(s: &str) -> Result<i32, String> {
+    pid1::parse_signal(s).ok_or_else(|| format!("invalid signal: `{s}`"))
+}
+
+/// Parse a duration such as 500ms, 10s or 1m
+#[cfg(target_family = "unix")]
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    pid1::parse_duration(s).ok_or_else(|| format!("invalid duration: `{s}`"))
+}
+
+/// Parse a FROM=TO signal rewrite, where TO may be IGNORE
+#[cfg(target_family = "unix")]
+fn parse_signal_rewrite(s: &str) -> Result<(i32, Option<i32>), String> {
+    let (from, to) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid FROM=TO: no `=` found in `{s}`"))?;
+    let to = if to.eq_ignore_ascii_case("IGNORE") {
+        None
+    } else {
+        Some(parse_signal(to)?)
+    };
+    Ok((parse_signal(from)?, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pid1App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        Pid1App::command().debug_assert();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn signal_options_need_supervision() {
+        use clap::Parser;
+        let needs_supervision = |args: &[&str]| {
+            let app = Pid1App::try_parse_from(args).unwrap();
+            app.needs_supervision()
+        };
+        assert!(!needs_supervision(&["pid1", "nginx"]));
+        assert!(needs_supervision(&[
+            "pid1",
+            "--rewrite-signal",
+            "TERM=QUIT",
+            "nginx"
+        ]));
+        assert!(needs_supervision(&[
+            "pid1",
+            "--forward-signal",
+            "HUP",
+            "nginx"
+        ]));
+        assert!(needs_supervision(&[
+            "pid1",
+            "--stop-sequence",
+            "QUIT:5s,KILL",
+            "nginx"
+        ]));
+    }
+}
@@ -0,0 +1,302 @@
+//! The `--config` file, in TOML. Every key is optional and uses the
+//! name of the corresponding command-line option:
+//!
+//! ```toml
+//! command = "my-server"
+//! args = ["--port", "8080"]
+//! workdir = "/app"
+//! user-id = 1000
+//! forward-signals = ["HUP", "USR1"]
+//! stop-sequence = "TERM:10s,KILL"
+//! success-exit-codes = [0, 143]
+//! metrics = "9100"
+//! control-socket = "/run/pid1.sock"
+//!
+//! [env]
+//! RUST_LOG = "info"
+//!
+//! [rewrite-signals]
+//! TERM = "QUIT"
+//!
+//! [restart]
+//! policy = "on-failure"
+//! max-restarts = 5
+//!
+//! [health-check]
+//! probe = "http:8080/healthz"
+//! action = "restart"
+//!
+//! [services.shipper]
+//! command = "fluent-bit"
+//! args = ["-c", "/etc/fluent-bit.conf"]
+//! critical = false
+//! ```
+
+use crate::metrics::MetricsAddress;
+use pid1::{HealthProbe, MainExitPolicy, Restart, ShutdownPolicy, UnhealthyAction};
+use serde::{de, Deserialize, Deserializer};
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf, str::FromStr, time::Duration};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Config {
+    pub(crate) command: Option<String>,
+    #[serde(default)]
+    pub(crate) args: Vec<String>,
+    pub(crate) workdir: Option<PathBuf>,
+    pub(crate) timeout: Option<u8>,
+    pub(crate) verbose: Option<bool>,
+    #[serde(default)]
+    pub(crate) env: BTreeMap<String, String>,
+    pub(crate) user_id: Option<u32>,
+    pub(crate) group_id: Option<u32>,
+    #[serde(default)]
+    pub(crate) forward_signals: Vec<Parsed<Signal>>,
+    #[serde(default)]
+    pub(crate) rewrite_signals: BTreeMap<Parsed<Signal>, Parsed<SignalRewrite>>,
+    pub(crate) process_group: Option<bool>,
+    pub(crate) tty: Option<bool>,
+    pub(crate) subreaper: Option<bool>,
+    pub(crate) stop_sequence: Option<Parsed<ShutdownPolicy>>,
+    pub(crate) on_main_exit: Option<Parsed<MainExitPolicy>>,
+    #[serde(default)]
+    pub(crate) map_exit: BTreeMap<Parsed<i32>, i32>,
+    #[serde(default)]
+    pub(crate) success_exit_codes: Vec<i32>,
+    pub(crate) metrics: Option<Parsed<MetricsAddress>>,
+    pub(crate) control_socket: Option<PathBuf>,
+    #[serde(default)]
+    pub(crate) restart: RestartConfig,
+    #[serde(default)]
+    pub(crate) health_check: HealthCheckConfig,
+    #[serde(default)]
+    pub(crate) services: BTreeMap<String, ServiceConfig>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct RestartConfig {
+    pub(crate) policy: Option<Parsed<Restart>>,
+    pub(crate) delay: Option<Parsed<Wait>>,
+    pub(crate) max_delay: Option<Parsed<Wait>>,
+    pub(crate) max_restarts: Option<u32>,
+    pub(crate) window: Option<Parsed<Wait>>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct HealthCheckConfig {
+    pub(crate) probe: Option<Parsed<HealthProbe>>,
+    pub(crate) interval: Option<Parsed<Wait>>,
+    pub(crate) timeout: Option<Parsed<Wait>>,
+    pub(crate) retries: Option<u32>,
+    pub(crate) action: Option<Parsed<UnhealthyAction>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct ServiceConfig {
+    pub(crate) command: String,
+    #[serde(default)]
+    pub(crate) args: Vec<String>,
+    pub(crate) workdir: Option<PathBuf>,
+    #[serde(default)]
+    pub(crate) env: BTreeMap<String, String>,
+    pub(crate) user_id: Option<u32>,
+    pub(crate) group_id: Option<u32>,
+    #[serde(default = "critical_by_default")]
+    pub(crate) critical: bool,
+}
+
+fn critical_by_default() -> bool {
+    true
+}
+
+impl Config {
+    /// Read and validate the configuration file at `path`.
+    pub(crate) fn load(path: &PathBuf) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        toml::from_str(&content)
+            .map_err(|err| format!("invalid {}: {}", path.display(), err.to_string().trim_end()))
+    }
+}
+
+/// A value parsed from a string with [`FromStr`], so that invalid
+/// values are reported along with their location in the file.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Parsed<T>(pub(crate) T);
+
+impl<'de, T> Deserialize<'de> for Parsed<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map(Parsed).map_err(de::Error::custom)
+    }
+}
+
+/// A signal name or number, see [`pid1::parse_signal`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Signal(pub(crate) i32);
+
+impl FromStr for Signal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        pid1::parse_signal(s)
+            .map(Signal)
+            .ok_or_else(|| format!("invalid signal: `{s}`"))
+    }
+}
+
+/// The target of a signal rewrite: a signal or `IGNORE`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct SignalRewrite(pub(crate) Option<i32>);
+
+impl FromStr for SignalRewrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("IGNORE") {
+            Ok(SignalRewrite(None))
+        } else {
+            s.parse().map(|Signal(signal)| SignalRewrite(Some(signal)))
+        }
+    }
+}
+
+/// A duration such as `500ms` or `10s`, see [`pid1::parse_duration`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Wait(pub(crate) Duration);
+
+impl FromStr for Wait {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        pid1::parse_duration(s)
+            .map(Wait)
+            .ok_or_else(|| format!("invalid duration: `{s}`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Pid1App;
+    use clap::{CommandFactory, FromArgMatches};
+
+    /// The settings from the command line `args` and the `config` file.
+    fn load(args: &[&str], config: &str) -> Pid1App {
+        let matches = Pid1App::command().try_get_matches_from(args).unwrap();
+        let mut app = Pid1App::from_arg_matches(&matches).unwrap();
+        app.apply_config(toml::from_str(config).unwrap(), &matches);
+        app
+    }
+
+    fn signal_number(name: &str) -> i32 {
+        pid1::parse_signal(name).unwrap()
+    }
+
+    #[test]
+    fn parse_config() {
+        let config: Config = toml::from_str(
+            r#"
+            command = "my-server"
+            args = ["--port", "8080"]
+            forward-signals = ["HUP", "10"]
+            stop-sequence = "TERM:10s,KILL"
+
+            [rewrite-signals]
+            TERM = "IGNORE"
+
+            [restart]
+            policy = "on-failure"
+            delay = "500ms"
+
+            [services.shipper]
+            command = "fluent-bit"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.command.as_deref(), Some("my-server"));
+        assert_eq!(config.args, ["--port", "8080"]);
+        assert_eq!(
+            config.forward_signals,
+            [Parsed(Signal(signal_number("HUP"))), Parsed(Signal(10))]
+        );
+        assert_eq!(
+            config.rewrite_signals.into_iter().collect::<Vec<_>>(),
+            [(
+                Parsed(Signal(signal_number("TERM"))),
+                Parsed(SignalRewrite(None))
+            )]
+        );
+        assert_eq!(config.restart.policy, Some(Parsed(Restart::OnFailure)));
+        assert_eq!(
+            config.restart.delay,
+            Some(Parsed(Wait(Duration::from_millis(500))))
+        );
+        assert!(config.services["shipper"].critical);
+    }
+
+    #[test]
+    fn reject_invalid_config() {
+        let error = |config| toml::from_str::<Config>(config).unwrap_err().to_string();
+        assert!(error("comand = \"sh\"").contains("unknown field `comand`"));
+        assert!(error("[restart]\ntries = 3").contains("unknown field `tries`"));
+        assert!(error("stop-sequence = \"TERM:10x\"").contains("stop-sequence"));
+        assert!(error("forward-signals = [\"NOPE\"]").contains("invalid signal: `NOPE`"));
+    }
+
+    #[test]
+    fn command_line_takes_precedence() {
+        let config = r#"
+            command = "from-file"
+            args = ["--flag"]
+            timeout = 5
+            verbose = true
+            forward-signals = ["HUP"]
+
+            [env]
+            FROM = "file"
+            "#;
+        let app = load(&["pid1", "--config", "pid1.toml"], config);
+        assert_eq!(app.command.as_deref(), Some("from-file"));
+        assert_eq!(app.args, ["--flag"]);
+        assert_eq!(app.timeout, 5);
+        assert!(app.verbose);
+        assert_eq!(app.forward_signal, [signal_number("HUP")]);
+
+        let app = load(
+            &[
+                "pid1",
+                "--config",
+                "pid1.toml",
+                "--timeout",
+                "9",
+                "--forward-signal",
+                "USR1",
+                "-e",
+                "FROM=cli",
+                "from-cli",
+            ],
+            config,
+        );
+        assert_eq!(app.command.as_deref(), Some("from-cli"));
+        assert!(app.args.is_empty());
+        assert_eq!(app.timeout, 9);
+        assert!(app.verbose);
+        assert_eq!(app.forward_signal, [signal_number("USR1")]);
+        // The environment is merged, with the command line last
+        assert_eq!(
+            app.env,
+            [
+                ("FROM".into(), "file".into()),
+                ("FROM".into(), "cli".into())
+            ]
+        );
+    }
+}
@@ -1,10 +1,12 @@
 mod cli;
-
-use clap::Parser;
+#[cfg(target_family = "unix")]
+mod config;
+#[cfg(target_family = "unix")]
+mod metrics;
 
 use crate::cli::Pid1App;
 
 fn main() {
-    let cli = Pid1App::parse();
+    let cli = Pid1App::load();
     cli.run()
 }
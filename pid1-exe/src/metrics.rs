@@ -0,0 +1,75 @@
+//! Serving the [`Metrics`] of the supervision for Prometheus to
+//! scrape, over HTTP on a TCP port or a Unix socket.
+
+use pid1::Metrics;
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Where to serve the metrics: `[IP:]PORT`, listening on all the
+/// interfaces by default, or `unix:PATH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MetricsAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl FromStr for MetricsAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            return Ok(MetricsAddress::Unix(path.into()));
+        }
+        if let Ok(port) = s.parse::<u16>() {
+            return Ok(MetricsAddress::Tcp((Ipv4Addr::UNSPECIFIED, port).into()));
+        }
+        s.parse()
+            .map(MetricsAddress::Tcp)
+            .map_err(|_| format!("invalid metrics address: `{s}`"))
+    }
+}
+
+/// Listen on `address` and answer every request with the `metrics`,
+/// from a background thread.
+pub(crate) fn serve(address: &MetricsAddress, metrics: Arc<Metrics>) -> io::Result<()> {
+    match address {
+        MetricsAddress::Tcp(addr) => {
+            let listener = TcpListener::bind(addr)?;
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+                    respond(stream, &metrics);
+                }
+            });
+        }
+        MetricsAddress::Unix(path) => {
+            let listener = pid1::bind_unix_socket(path)?;
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+                    respond(stream, &metrics);
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Answer a single HTTP request, whatever it is, with the metrics.
+fn respond(mut stream: impl Read + Write, metrics: &Metrics) {
+    let _ = stream.read(&mut [0; 1024]);
+    let body = metrics.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.0 200 OK\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+}
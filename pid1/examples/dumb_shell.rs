@@ -16,7 +16,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .split(' ')
                 .map(|item| OsString::from_str(item).unwrap())
                 .collect();
-            let exe = args.get(0).unwrap();
+            let exe = args.first().unwrap();
             if exe == "exit" {
                 std::process::exit(0);
             }
@@ -13,7 +13,7 @@ fn main() {
         .expect("Launch failed");
     println!("This APP can be killed by SIGTERM (15)");
     let mut signals = Signals::new([SIGTERM]).unwrap();
-    for signal in signals.forever() {
+    if let Some(signal) = signals.forever().next() {
         println!("App got SIGTERM {signal}, going to exit");
         std::process::exit(0);
     }
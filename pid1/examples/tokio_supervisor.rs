@@ -0,0 +1,17 @@
+use pid1::{tokio::Supervisor, Pid1Settings};
+use tokio::process::Command;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let program = args
+        .next()
+        .ok_or("Usage: tokio_supervisor COMMAND [ARGS]...")?;
+    let mut settings = Pid1Settings::new();
+    settings.enable_log(true);
+    let supervisor = Supervisor::spawn(&settings, Command::new(program).args(args))?;
+    println!("Supervising PID {}", supervisor.id());
+    let outcome = supervisor.wait().await?;
+    println!("Child ended with {:?}", outcome.status);
+    std::process::exit(outcome.exit_code())
+}
@@ -0,0 +1,56 @@
+use crate::signal::Waker;
+use std::{
+    sync::mpsc::{channel, RecvTimeoutError, Sender},
+    time::Instant,
+};
+
+/// Wakes up the signal loop at a given deadline.
+///
+/// The signal loop blocks on [`signal_hook::iterator::Signals`], so
+/// the only way to wake it up is a signal, sent with a [`Waker`].
+pub(crate) struct Alarm {
+    waker: Waker,
+    sender: Option<Sender<Instant>>,
+}
+
+impl Alarm {
+    /// An alarm for the calling thread, which runs the signal loop.
+    pub(crate) fn new() -> Self {
+        Self {
+            waker: Waker::current(),
+            sender: None,
+        }
+    }
+
+    /// Make sure the signal loop wakes up at `deadline`. Waking up
+    /// earlier than that is possible when other deadlines are set.
+    pub(crate) fn set(&mut self, deadline: Instant) {
+        let waker = self.waker;
+        let sender = self.sender.get_or_insert_with(|| {
+            let (sender, receiver) = channel::<Instant>();
+            std::thread::spawn(move || {
+                let mut next: Option<Instant> = None;
+                loop {
+                    let received = match next {
+                        None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                        Some(next) => {
+                            receiver.recv_timeout(next.saturating_duration_since(Instant::now()))
+                        }
+                    };
+                    match received {
+                        Ok(deadline) => {
+                            next = Some(next.map_or(deadline, |next| next.min(deadline)));
+                        }
+                        Err(RecvTimeoutError::Timeout) => {
+                            next = None;
+                            waker.wake();
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+            });
+            sender
+        });
+        let _ = sender.send(deadline);
+    }
+}
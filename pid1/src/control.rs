@@ -0,0 +1,194 @@
+use crate::signal::{self, Waker};
+use std::{
+    ffi::c_int,
+    fmt,
+    fmt::Write as _,
+    fs::Permissions,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, Sender},
+    time::Duration,
+};
+
+/// A command received on the control socket, see
+/// [`crate::Pid1Settings::control_socket`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// Describe the state of the supervision and of each child.
+    Status,
+    /// Act as if PID 1 received the signal, which has to be one it
+    /// handles.
+    Signal(c_int),
+    /// Stop the children with the shutdown policy and start them again.
+    Restart,
+    /// Stop the children as if PID 1 received `SIGTERM`.
+    Stop,
+    /// List the processes PID 1 is responsible for.
+    Ps,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        match words[..] {
+            ["status"] => Ok(Command::Status),
+            ["signal", name] => signal::parse_signal(name)
+                .map(Command::Signal)
+                .ok_or_else(|| format!("unknown signal `{name}`")),
+            ["restart"] => Ok(Command::Restart),
+            ["stop"] => Ok(Command::Stop),
+            ["ps"] => Ok(Command::Ps),
+            _ => Err(format!(
+                "unknown command `{}`, expected `status`, `signal NAME`, `restart`, `stop` or `ps`",
+                s.trim()
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Status => f.write_str("status"),
+            Command::Signal(signal) => write!(f, "signal {}", signal::signal_name(*signal)),
+            Command::Restart => f.write_str("restart"),
+            Command::Stop => f.write_str("stop"),
+            Command::Ps => f.write_str("ps"),
+        }
+    }
+}
+
+/// A [`Command`] waiting for the supervision to handle it.
+pub(crate) struct Request {
+    pub(crate) command: Command,
+    reply: Sender<Result<String, String>>,
+}
+
+impl Request {
+    /// Answer the client with the output of the command, or why it
+    /// failed.
+    pub(crate) fn reply(self, response: Result<String, String>) {
+        let _ = self.reply.send(response);
+    }
+}
+
+/// A Unix socket accepting [`Command`]s, one per connection. A
+/// background thread reads them and passes them to the supervision,
+/// waking it up with a [`Waker`]. The client gets `ok` followed by
+/// the output of the command, or `error: ` followed by the reason it
+/// failed. Only the owner can connect to it, as the commands stop and
+/// signal the processes.
+pub(crate) struct ControlSocket {
+    path: PathBuf,
+    requests: Receiver<Request>,
+}
+
+impl ControlSocket {
+    /// Listen on `path`, see [`bind_unix_socket`].
+    pub(crate) fn bind(path: &Path) -> io::Result<Self> {
+        let listener = bind_unix_socket(path)?;
+        std::fs::set_permissions(path, Permissions::from_mode(0o600))?;
+        let (sender, requests) = mpsc::channel();
+        let waker = Waker::current();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                serve(stream, &sender, &waker);
+            }
+        });
+        Ok(Self {
+            path: path.to_owned(),
+            requests,
+        })
+    }
+
+    /// The next request to handle, if any.
+    pub(crate) fn next_request(&self) -> Option<Request> {
+        self.requests.try_recv().ok()
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        // The thread keeps accepting connections, which fail without
+        // the supervision, but no new client can find it
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Listen on a Unix socket at `path`, replacing a socket left over by
+/// a previous run. Anything else at `path` is left alone, and fails
+/// with [`io::ErrorKind::AlreadyExists`].
+pub fn bind_unix_socket(path: &Path) -> io::Result<UnixListener> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "the file exists and is not a socket",
+            ))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    UnixListener::bind(path)
+}
+
+/// Read a command from `stream` and write back the response.
+fn serve(stream: UnixStream, sender: &Sender<Request>, waker: &Waker) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line).is_err() {
+        return;
+    }
+    let response = line.parse().and_then(|command| {
+        let (reply, response) = mpsc::channel();
+        sender
+            .send(Request { command, reply })
+            .map_err(|_| "the supervision is over".to_owned())?;
+        waker.wake();
+        response
+            .recv_timeout(Duration::from_secs(5))
+            .map_err(|_| "no response from the supervision".to_owned())?
+    });
+    let mut stream = &stream;
+    let _ = match response {
+        Ok(output) => write!(stream, "ok\n{output}"),
+        Err(e) => writeln!(stream, "error: {e}"),
+    };
+}
+
+/// Output of the `ps` command: the PID, parent PID, state and command
+/// line of the processes PID 1 is responsible for.
+pub(crate) fn ps() -> String {
+    let mut output = "PID PPID STATE COMMAND\n".to_owned();
+    for pid in signal::descendants() {
+        let proc = PathBuf::from(format!("/proc/{pid}"));
+        let Ok(stat) = std::fs::read_to_string(proc.join("stat")) else {
+            continue;
+        };
+        // The fields after the command name, which is in parenthesis
+        // and may contain spaces, start with the state and parent PID
+        let Some((comm, rest)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let mut fields = rest.split_whitespace();
+        let (Some(state), Some(ppid)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let cmdline = std::fs::read(proc.join("cmdline")).unwrap_or_default();
+        let command = match String::from_utf8_lossy(&cmdline).replace('\0', " ").trim() {
+            // Kernel threads and zombies have no command line
+            "" => format!("[{}]", comm.split_once('(').map_or("", |(_, comm)| comm)),
+            command => command.to_owned(),
+        };
+        let _ = writeln!(output, "{pid} {ppid} {state} {command}");
+    }
+    output
+}
@@ -0,0 +1,187 @@
+use crate::{
+    logging::event,
+    supervise::{Progress, Supervision, SupervisionOutcome},
+    Pid1Settings,
+};
+use nix::{
+    errno::Errno,
+    sys::{
+        epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout},
+        signal::SigSet,
+        signalfd::{SfdFlags, SignalFd},
+        time::TimeSpec,
+        timerfd::{ClockId, Expiration, TimerFd, TimerFlags, TimerSetTimeFlags},
+    },
+};
+use signal_hook::{iterator::Signals, low_level, SigId};
+use std::{
+    ffi::c_int,
+    io::{self, Read},
+    os::unix::net::UnixStream,
+    time::{Duration, Instant},
+};
+
+const SIGNAL: u64 = 0;
+const TIMER: u64 = 1;
+const CHILD: u64 = 2;
+const WAKE: u64 = 3;
+
+/// Single-threaded event loop receiving signals through a `signalfd`,
+/// waking up at deadlines through a `timerfd` and watching the pidfds
+/// of the children, all multiplexed with `epoll`.
+///
+/// The handled signals are blocked in the calling thread while the
+/// event loop exists, so that they are only received through the
+/// `signalfd`.
+/// They are unblocked while polling the supervision, as the children
+/// it respawns would inherit the signal mask.
+/// Signals delivered to the other threads of the process are still
+/// received through signal-hook, which wakes up the event loop by
+/// writing to a socket.
+pub(crate) struct EventLoop {
+    epoll: Epoll,
+    signal_fd: SignalFd,
+    timer: TimerFd,
+    mask: SigSet,
+    wake: UnixStream,
+    wake_actions: Vec<SigId>,
+}
+
+impl EventLoop {
+    /// Set up the event loop for the given `signals`.
+    pub(crate) fn new(signals: &[c_int]) -> io::Result<Self> {
+        // `SigSet::add` doesn't support real-time signals
+        let mask = unsafe {
+            let mut mask = std::mem::zeroed::<libc::sigset_t>();
+            libc::sigemptyset(&mut mask);
+            for &signal in signals {
+                libc::sigaddset(&mut mask, signal);
+            }
+            SigSet::from_sigset_t_unchecked(mask)
+        };
+        let epoll = Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC)?;
+        let flags = TimerFlags::TFD_NONBLOCK | TimerFlags::TFD_CLOEXEC;
+        let timer = TimerFd::new(ClockId::CLOCK_MONOTONIC, flags)?;
+        let flags = SfdFlags::SFD_NONBLOCK | SfdFlags::SFD_CLOEXEC;
+        let signal_fd = SignalFd::with_flags(&mask, flags)?;
+        epoll.add(&signal_fd, EpollEvent::new(EpollFlags::EPOLLIN, SIGNAL))?;
+        epoll.add(&timer, EpollEvent::new(EpollFlags::EPOLLIN, TIMER))?;
+        let (wake, waker) = UnixStream::pair()?;
+        wake.set_nonblocking(true)?;
+        epoll.add(&wake, EpollEvent::new(EpollFlags::EPOLLIN, WAKE))?;
+        let mut event_loop = Self {
+            epoll,
+            signal_fd,
+            timer,
+            mask,
+            wake,
+            wake_actions: Vec::new(),
+        };
+        for &signal in signals {
+            let action = low_level::pipe::register(signal, waker.try_clone()?)?;
+            event_loop.wake_actions.push(action);
+        }
+        event_loop.mask.thread_block()?;
+        Ok(event_loop)
+    }
+
+    /// Supervise the children until they exit. `signals` is used for
+    /// the signals received before the event loop was set up, or by
+    /// other threads.
+    pub(crate) fn run(
+        self,
+        mut supervision: Supervision<'_>,
+        mut signals: Signals,
+    ) -> SupervisionOutcome {
+        let settings = supervision.settings();
+        for signal in signals.pending() {
+            supervision.received(signal);
+        }
+        let mut events = [EpollEvent::empty(); 8];
+        loop {
+            // Signals received meanwhile go through signal-hook
+            let _ = self.mask.thread_unblock();
+            let progress = supervision.poll();
+            let _ = self.mask.thread_block();
+            match progress {
+                Progress::Running(deadline) => self.set_timer(settings, deadline),
+                Progress::Finished(outcome) => return outcome,
+            }
+            for pidfd in supervision.pidfds() {
+                // Closed pidfds are removed from the epoll set by the
+                // kernel, so only new ones are actually added
+                match self
+                    .epoll
+                    .add(pidfd, EpollEvent::new(EpollFlags::EPOLLIN, CHILD))
+                {
+                    Ok(()) | Err(Errno::EEXIST) => {}
+                    Err(e) => event!(settings, Warn, "Failed to watch a pidfd: {e}"),
+                }
+            }
+            let count = match self.epoll.wait(&mut events, EpollTimeout::NONE) {
+                Ok(count) => count,
+                Err(Errno::EINTR) => continue,
+                Err(e) => {
+                    event!(settings, Error, "Error in epoll_wait: {e}");
+                    continue;
+                }
+            };
+            for event in &events[..count] {
+                match event.data() {
+                    SIGNAL => loop {
+                        match self.signal_fd.read_signal() {
+                            Ok(Some(info)) => supervision.received(info.ssi_signo as c_int),
+                            Ok(None) => break,
+                            Err(e) => {
+                                event!(settings, Error, "Error reading the signalfd: {e}");
+                                break;
+                            }
+                        }
+                    },
+                    // The deadlines are checked by `poll`
+                    TIMER => {
+                        let _ = self.timer.wait();
+                    }
+                    WAKE => {
+                        while matches!((&self.wake).read(&mut [0; 64]), Ok(1..)) {}
+                        for signal in signals.pending() {
+                            supervision.received(signal);
+                        }
+                    }
+                    // SIGCHLD follows, but there is no need to wait for it
+                    _ => supervision.reap(),
+                }
+            }
+        }
+    }
+
+    /// Make the event loop wake up at `deadline`, if any.
+    fn set_timer(&self, settings: &Pid1Settings, deadline: Option<Instant>) {
+        let result = match deadline {
+            // A zero expiration would disarm the timer
+            Some(deadline) => self.timer.set(
+                Expiration::OneShot(TimeSpec::from_duration(
+                    deadline
+                        .saturating_duration_since(Instant::now())
+                        .max(Duration::from_nanos(1)),
+                )),
+                TimerSetTimeFlags::empty(),
+            ),
+            None => self.timer.unset(),
+        };
+        if let Err(e) = result {
+            event!(settings, Error, "Failed to set the timerfd: {e}");
+        }
+    }
+}
+
+impl Drop for EventLoop {
+    fn drop(&mut self) {
+        // Signals still pending get delivered to the signal-hook
+        // handlers again
+        let _ = self.mask.thread_unblock();
+        for action in self.wake_actions.drain(..) {
+            low_level::unregister(action);
+        }
+    }
+}
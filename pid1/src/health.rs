@@ -0,0 +1,419 @@
+#[cfg(target_family = "unix")]
+use crate::{
+    signal::{ChildProcess, Waker},
+    ChildStatus,
+};
+#[cfg(target_family = "unix")]
+use signal_hook::consts::SIGKILL;
+#[cfg(target_family = "unix")]
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    process::{Command, Stdio},
+    sync::mpsc::{channel, Receiver, TryRecvError},
+    time::Instant,
+};
+use std::{
+    net::{Ipv4Addr, SocketAddr, ToSocketAddrs},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{split_command, ParseError};
+
+/// What a [`HealthCheck`] probes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthProbe {
+    /// Run `program` with `args`. The child is healthy when it exits
+    /// with code 0.
+    Exec { program: String, args: Vec<String> },
+    /// Connect to a TCP address. The child is healthy when the
+    /// connection is accepted.
+    Tcp(SocketAddr),
+    /// Send an HTTP `GET` request for `path`. The child is healthy when
+    /// the response has a 2xx or 3xx status.
+    Http { addr: SocketAddr, path: String },
+}
+
+/// Parses `exec:COMMAND`, `tcp:[HOST:]PORT` or
+/// `http:[HOST:]PORT[/PATH]`. `COMMAND` is split on whitespace, `HOST`
+/// defaults to `127.0.0.1` and `PATH` to `/`.
+impl FromStr for HealthProbe {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason| ParseError {
+            value: s.to_owned(),
+            reason,
+        };
+        let (kind, target) = s
+            .split_once(':')
+            .ok_or_else(|| error("expected `exec:`, `tcp:` or `http:`"))?;
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "exec" => {
+                let mut words = split_command(target)
+                    .ok_or_else(|| error("unbalanced quotes"))?
+                    .into_iter();
+                let program = words.next().ok_or_else(|| error("missing command"))?;
+                Ok(HealthProbe::Exec {
+                    program,
+                    args: words.collect(),
+                })
+            }
+            "tcp" => parse_address(target)
+                .map(HealthProbe::Tcp)
+                .ok_or_else(|| error("invalid address")),
+            "http" => {
+                let target = target.trim().trim_start_matches("//");
+                let (addr, path) = match target.find('/') {
+                    Some(split) => target.split_at(split),
+                    None => (target, "/"),
+                };
+                Ok(HealthProbe::Http {
+                    addr: parse_address(addr).ok_or_else(|| error("invalid address"))?,
+                    path: path.to_owned(),
+                })
+            }
+            _ => Err(error("expected `exec:`, `tcp:` or `http:`")),
+        }
+    }
+}
+
+/// Parses `PORT` as a port on `127.0.0.1`, or `HOST:PORT`.
+fn parse_address(s: &str) -> Option<SocketAddr> {
+    let s = s.trim();
+    if let Ok(port) = s.parse::<u16>() {
+        return Some(SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+    }
+    s.to_socket_addrs().ok()?.next()
+}
+
+/// What PID 1 does once a [`HealthCheck`] failed too many times in a
+/// row.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnhealthyAction {
+    /// Only log that the child is unhealthy.
+    #[default]
+    Log,
+    /// Stop the child following the [`crate::Pid1Settings::shutdown_policy`]
+    /// and start it again right away, whatever the
+    /// [`crate::RestartPolicy`]. When the child cannot be spawned again,
+    /// this acts like [`UnhealthyAction::Shutdown`].
+    Restart,
+    /// Stop all the children as if PID 1 received `SIGTERM`.
+    Shutdown,
+}
+
+/// Parses `log`, `restart` or `shutdown`.
+impl FromStr for UnhealthyAction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "log" => Ok(UnhealthyAction::Log),
+            "restart" => Ok(UnhealthyAction::Restart),
+            "shutdown" => Ok(UnhealthyAction::Shutdown),
+            _ => Err(ParseError {
+                value: s.to_owned(),
+                reason: "expected `log`, `restart` or `shutdown`",
+            }),
+        }
+    }
+}
+
+/// A periodic check of the health of a child process. The probe is
+/// run every interval, starting one interval after the child was
+/// spawned, and fails when it doesn't complete within the timeout.
+/// Once it fails the given number of times in a row, the child is
+/// considered unhealthy and the [`UnhealthyAction`] is taken.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use pid1::{HealthCheck, UnhealthyAction};
+///
+/// let check = HealthCheck::new("http:8080/healthz".parse().unwrap())
+///     .interval(Duration::from_secs(5))
+///     .failure_threshold(3)
+///     .action(UnhealthyAction::Restart);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheck {
+    probe: HealthProbe,
+    interval: Duration,
+    timeout: Duration,
+    failure_threshold: u32,
+    action: UnhealthyAction,
+}
+
+impl HealthCheck {
+    /// A check running `probe` every 10 seconds with a timeout of 5
+    /// seconds, which logs once the probe failed 3 times in a row.
+    pub fn new(probe: HealthProbe) -> Self {
+        Self {
+            probe,
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(5),
+            failure_threshold: 3,
+            action: UnhealthyAction::Log,
+        }
+    }
+
+    /// Time between the start of two probes.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Time after which a probe which hasn't completed fails.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Number of failed probes in a row after which the child is
+    /// unhealthy. It is at least 1.
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    /// What to do once the child is unhealthy.
+    pub fn action(mut self, action: UnhealthyAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// What this check probes.
+    pub fn probe(&self) -> &HealthProbe {
+        &self.probe
+    }
+}
+
+/// A probe which hasn't completed yet.
+#[cfg(target_family = "unix")]
+enum Running {
+    /// An exec probe. Its result comes from the supervision loop
+    /// reaping it. Once `killed`, it has timed out already.
+    Process { process: ChildProcess, killed: bool },
+    /// A network probe, run on its own thread which raises `SIGCHLD`
+    /// to wake up the supervision loop once done.
+    Thread(Receiver<Result<(), String>>),
+}
+
+/// The result of a completed probe.
+#[cfg(target_family = "unix")]
+pub(crate) struct Probed {
+    pub(crate) result: Result<(), String>,
+    /// Number of failed probes in a row, including this one.
+    pub(crate) failures: u32,
+    pub(crate) threshold: u32,
+}
+
+/// Runs the [`HealthCheck`] of a supervised child and counts its
+/// failures.
+#[cfg(target_family = "unix")]
+pub(crate) struct Monitor {
+    check: HealthCheck,
+    next_probe: Instant,
+    running: Option<(Running, Instant)>,
+    result: Option<Result<(), String>>,
+    failures: u32,
+}
+
+#[cfg(target_family = "unix")]
+impl Monitor {
+    pub(crate) fn new(check: HealthCheck) -> Self {
+        Self {
+            next_probe: Instant::now() + check.interval,
+            check,
+            running: None,
+            result: None,
+            failures: 0,
+        }
+    }
+
+    /// Start over for a child which was just spawned again.
+    pub(crate) fn reset(&mut self) {
+        self.next_probe = Instant::now() + self.check.interval;
+        self.result = None;
+        self.failures = 0;
+    }
+
+    /// The pidfd of the running exec probe, if any.
+    #[cfg(all(target_os = "linux", feature = "epoll"))]
+    pub(crate) fn pidfd(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        match &self.running {
+            Some((Running::Process { process, .. }, _)) => process.pidfd(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn action(&self) -> UnhealthyAction {
+        self.check.action
+    }
+
+    /// Record that `pid` was reaped, returning whether it was the exec
+    /// probe.
+    pub(crate) fn reaped(&mut self, pid: i32, status: &ChildStatus) -> bool {
+        let Some((Running::Process { process, killed }, _)) = &self.running else {
+            return false;
+        };
+        if process.pid() != pid {
+            return false;
+        }
+        if !killed {
+            self.result = Some(match status.exit_code() {
+                0 => Ok(()),
+                exit_code => Err(format!("exited with code {exit_code}")),
+            });
+        }
+        self.running = None;
+        true
+    }
+
+    /// Start the probe when it is due and collect its result. Returns
+    /// the completed probe, if any, and when to poll again.
+    pub(crate) fn poll(&mut self) -> (Option<Probed>, Instant) {
+        let now = Instant::now();
+        match &mut self.running {
+            Some((Running::Thread(receiver), _)) => match receiver.try_recv() {
+                Ok(result) => {
+                    self.result = Some(result);
+                    self.running = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.result = Some(Err("probe thread panicked".to_owned()));
+                    self.running = None;
+                }
+            },
+            Some((Running::Process { .. }, _)) | None => {}
+        }
+        match &mut self.running {
+            Some((running, deadline)) if now >= *deadline => {
+                let timeout = self.check.timeout;
+                match running {
+                    Running::Process { killed: true, .. } => {}
+                    Running::Process { process, killed } => {
+                        // It is reaped later on, as usual
+                        let _ = process.send_signal(SIGKILL);
+                        *killed = true;
+                        self.result = Some(Err(format!("timed out after {timeout:?}")));
+                    }
+                    Running::Thread(_) => {
+                        self.result = Some(Err(format!("timed out after {timeout:?}")));
+                        self.running = None;
+                    }
+                }
+            }
+            Some(_) => {}
+            None if now >= self.next_probe => {
+                self.next_probe = now + self.check.interval;
+                let deadline = now + self.check.timeout;
+                match self.start() {
+                    Ok(running) => self.running = Some((running, deadline)),
+                    Err(e) => self.result = Some(Err(e)),
+                }
+            }
+            None => {}
+        }
+        let probed = self.result.take().map(|result| {
+            match result {
+                Ok(()) => self.failures = 0,
+                Err(_) => self.failures += 1,
+            }
+            Probed {
+                result,
+                failures: self.failures,
+                threshold: self.check.failure_threshold,
+            }
+        });
+        let next_poll = match &self.running {
+            Some((Running::Process { killed: true, .. }, _)) => self.next_probe,
+            Some((_, deadline)) => *deadline,
+            None => self.next_probe,
+        };
+        (probed, next_poll)
+    }
+
+    fn start(&self) -> Result<Running, String> {
+        let timeout = self.check.timeout;
+        let probe = match &self.check.probe {
+            HealthProbe::Exec { program, args } => {
+                return Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map(|child| Running::Process {
+                        process: ChildProcess::new(child.id() as i32),
+                        killed: false,
+                    })
+                    .map_err(|e| format!("failed to run {program}: {e}"));
+            }
+            HealthProbe::Tcp(addr) => {
+                let addr = *addr;
+                Box::new(move || {
+                    TcpStream::connect_timeout(&addr, timeout)
+                        .map(drop)
+                        .map_err(|e| format!("failed to connect to {addr}: {e}"))
+                }) as Box<dyn FnOnce() -> Result<(), String> + Send>
+            }
+            HealthProbe::Http { addr, path } => {
+                let (addr, path) = (*addr, path.clone());
+                Box::new(move || http_get(addr, &path, timeout))
+            }
+        };
+        let (sender, receiver) = channel();
+        let waker = Waker::current();
+        std::thread::Builder::new()
+            .name("pid1-health".to_owned())
+            .spawn(move || {
+                // Once the supervision is over, there is nothing to
+                // wake up anymore
+                if sender.send(probe()).is_ok() {
+                    waker.wake();
+                }
+            })
+            .map_err(|e| format!("failed to start the probe: {e}"))?;
+        Ok(Running::Thread(receiver))
+    }
+}
+
+/// Send a `GET` request for `path` and check the status of the
+/// response.
+#[cfg(target_family = "unix")]
+fn http_get(addr: SocketAddr, path: &str, timeout: Duration) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("GET http://{addr}{path} failed: {e}");
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(error)?;
+    stream.set_read_timeout(Some(timeout)).map_err(error)?;
+    stream.set_write_timeout(Some(timeout)).map_err(error)?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.0\r\nHost: {addr}\r\nUser-Agent: pid1\r\nConnection: close\r\n\r\n"
+    )
+    .map_err(error)?;
+    // The status line is all we need, e.g. `HTTP/1.1 200 OK`
+    let mut response = [0; 32];
+    let mut read = 0;
+    while read < response.len() {
+        match stream.read(&mut response[read..]).map_err(error)? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    let response = String::from_utf8_lossy(&response[..read]);
+    let status = response
+        .strip_prefix("HTTP/")
+        .and_then(|rest| rest.split_whitespace().nth(1))
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| format!("GET http://{addr}{path}: invalid response"))?;
+    if (200..400).contains(&status) {
+        Ok(())
+    } else {
+        Err(format!("GET http://{addr}{path} returned {status}"))
+    }
+}
@@ -1,26 +1,145 @@
 #[cfg(target_family = "unix")]
-use nix::{
-    sys::{signal::kill, wait::WaitStatus},
-    unistd::Pid,
-};
-#[cfg(target_family = "unix")]
 use signal_hook::{
-    consts::{SIGCHLD, SIGINT, SIGTERM},
+    consts::{FORBIDDEN, SIGCHLD, SIGINT, SIGTERM},
     iterator::Signals,
 };
-#[cfg(target_family = "unix")]
+use std::collections::BTreeMap;
 use std::ffi::c_int;
 #[cfg(target_family = "unix")]
+use std::fmt;
+#[cfg(target_family = "unix")]
+use std::fs::{File, OpenOptions};
+#[cfg(target_os = "linux")]
+use std::os::unix::fs::OpenOptionsExt;
+#[cfg(target_family = "unix")]
+use std::os::{fd::AsRawFd, unix::process::CommandExt};
+use std::path::PathBuf;
+#[cfg(target_family = "unix")]
 use std::process::Child;
+#[cfg(target_family = "unix")]
+use std::sync::Arc;
 use std::time::Duration;
 
-/// The `Error` enum indicates that the [`relaunch_if_pid1`] was not
-/// successful.
+#[cfg(target_family = "unix")]
+mod alarm;
+#[cfg(target_family = "unix")]
+mod control;
+#[cfg(all(target_os = "linux", feature = "epoll"))]
+mod epoll;
+mod health;
+mod logging;
+#[cfg(target_family = "unix")]
+mod metrics;
+#[cfg(target_family = "unix")]
+mod observer;
+mod parse;
+mod restart;
+#[cfg(target_family = "unix")]
+mod service;
+mod shutdown;
+#[cfg(target_family = "unix")]
+mod signal;
+#[cfg(target_family = "unix")]
+mod supervise;
+#[cfg(all(target_family = "unix", feature = "tokio"))]
+pub mod tokio;
+#[cfg(target_family = "unix")]
+mod tty;
+
+#[cfg(target_family = "unix")]
+pub use control::bind_unix_socket;
+pub use health::{HealthCheck, HealthProbe, UnhealthyAction};
+use logging::event;
+#[cfg(target_family = "unix")]
+pub use metrics::Metrics;
+#[cfg(target_family = "unix")]
+pub use observer::Pid1Observer;
+pub use parse::{split_command, ParseError};
+pub use restart::{Restart, RestartPolicy};
+#[cfg(target_family = "unix")]
+pub use service::Service;
+pub use shutdown::{parse_duration, MainExitPolicy, ShutdownPolicy, ShutdownStep};
+#[cfg(target_family = "unix")]
+pub use signal::{catchable_signals, parse_signal, signal_name};
+#[cfg(target_family = "unix")]
+use supervise::Supervision;
+#[cfg(target_family = "unix")]
+pub use supervise::{ChildStatus, ReapedProcess, SupervisionOutcome};
+
+/// The `Error` enum indicates that the [`relaunch_if_pid1`] or one of
+/// the steps to set up the supervision was not successful.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// Failed when respawning of non-PID1 child process
     #[error("Failed when respawning non-PID1 child process: {0}")]
     SpawnChild(std::io::Error),
+    /// Failed to mark the process as a child subreaper
+    #[error("Failed to become a child subreaper: {0}")]
+    Subreaper(std::io::Error),
+    /// Failed to install the signal handlers
+    #[error("Failed to register signal handlers: {0}")]
+    SignalRegistration(std::io::Error),
+    /// Failed to find the path of the current executable, which is
+    /// needed to relaunch it. This happens when `/proc` is not mounted.
+    #[error("Failed to find the current executable: {0}")]
+    CurrentExe(std::io::Error),
+    /// Failed to wait for the child process
+    #[error("Failed to wait for the child process: {0}")]
+    Wait(std::io::Error),
+}
+
+impl Error {
+    /// Conventional exit code for PID 1 to exit with when failing with
+    /// this error: `127` when the executable to run could not be found,
+    /// `126` when it could not be run and `1` otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::SpawnChild(e) | Error::CurrentExe(e) => spawn_failure_exit_code(e),
+            Error::Subreaper(_) | Error::SignalRegistration(_) | Error::Wait(_) => 1,
+        }
+    }
+}
+
+/// Exit code of a shell failing to run a command: `127` when the
+/// executable could not be found and `126` otherwise.
+fn spawn_failure_exit_code(e: &std::io::Error) -> i32 {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => 127,
+        _ => 126,
+    }
+}
+
+/// Environment variable set on the relaunched child, so that it
+/// doesn't relaunch itself again when running as a child subreaper.
+#[cfg(target_family = "unix")]
+const RELAUNCHED_ENV: &str = "PID1_RS_RELAUNCHED";
+
+/// How [`Pid1Settings::launch`] runs the current executable again as
+/// the child process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RelaunchStrategy {
+    /// Run the file at [`std::env::current_exe`]. This runs a different
+    /// program if the file was replaced since the process started, and
+    /// fails if it was deleted.
+    #[default]
+    Path,
+    /// Open `/proc/self/exe` and run the file it refers to through its
+    /// file descriptor, like `fexecve` does. The child is guaranteed to
+    /// be the same binary as PID 1, even if the file was replaced or
+    /// deleted since. The file only has to be executable, not
+    /// readable. Linux only.
+    FileDescriptor,
+}
+
+/// Mark the current process as a child subreaper: orphaned
+/// descendants get reparented to it instead of PID 1, so it can reap
+/// them. This is only supported on Linux.
+#[cfg(target_family = "unix")]
+pub fn become_child_subreaper() -> Result<(), Error> {
+    #[cfg(target_os = "linux")]
+    return nix::sys::prctl::set_child_subreaper(true).map_err(|e| Error::Subreaper(e.into()));
+    #[cfg(not(target_os = "linux"))]
+    Err(Error::Subreaper(std::io::ErrorKind::Unsupported.into()))
 }
 
 /// Relaunch process as PID with default value of [`Pid1Settings`]
@@ -35,11 +154,41 @@ pub fn relaunch_if_pid1() -> Result<(), Error> {
 }
 
 /// Settings for Pid1. The [`std::default::Default::default`] setting
-/// doesn't log and has a timeout of 2 seconds.
-#[derive(Debug, Copy, Clone)]
+/// doesn't log, has a timeout of 2 seconds and forwards all the
+/// [`catchable_signals`] to the child process.
+#[derive(Debug, Clone)]
 pub struct Pid1Settings {
     log: bool,
     timeout: Duration,
+    forward_signals: Vec<c_int>,
+    rewrite_signals: BTreeMap<c_int, Option<c_int>>,
+    process_group: bool,
+    tty: bool,
+    subreaper: bool,
+    shutdown_policy: Option<ShutdownPolicy>,
+    on_main_exit: MainExitPolicy,
+    restart_policy: RestartPolicy,
+    health_check: Option<HealthCheck>,
+    control_socket: Option<PathBuf>,
+    exit_code_map: BTreeMap<i32, i32>,
+    #[cfg(target_family = "unix")]
+    observer: Option<Arc<dyn Pid1Observer>>,
+    #[cfg(target_family = "unix")]
+    relaunch_hook: Option<RelaunchHook>,
+    relaunch_strategy: RelaunchStrategy,
+}
+
+/// Adjusts the relaunched command, see
+/// [`Pid1Settings::configure_relaunch`].
+#[cfg(target_family = "unix")]
+#[derive(Clone)]
+struct RelaunchHook(Arc<dyn Fn(&mut std::process::Command) + Send + Sync>);
+
+#[cfg(target_family = "unix")]
+impl fmt::Debug for RelaunchHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RelaunchHook")
+    }
 }
 
 impl Pid1Settings {
@@ -50,21 +199,240 @@ impl Pid1Settings {
     /// Should the crate log to [`std::io::Stderr`]. This can be
     /// useful to detect whether it is running with PID 1. By default
     /// it is 'false'.
+    ///
+    /// This has no effect with the `log` or `tracing` features, which
+    /// send all the diagnostics to the respective facade instead. Make
+    /// sure to set up the logger before calling
+    /// [`Pid1Settings::launch`] in that case.
     pub fn enable_log(&mut self, enable_log: bool) -> &mut Self {
         self.log = enable_log;
         self
     }
 
     /// Duration to wait for the child process to exit. By default it
-    /// is 2 seconds.
+    /// is 2 seconds. This has no effect when a
+    /// [`Pid1Settings::shutdown_policy`] is set.
     pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
         self.timeout = timeout;
         self
     }
 
+    /// Steps taken to stop the child process once PID 1 receives
+    /// `SIGTERM` or `SIGINT`. By default the received signal is sent,
+    /// followed by `SIGKILL` after [`Pid1Settings::timeout`].
+    pub fn shutdown_policy(&mut self, policy: ShutdownPolicy) -> &mut Self {
+        self.shutdown_policy = Some(policy);
+        self
+    }
+
+    /// What to do with the remaining processes once the main child
+    /// has exited. By default PID 1 exits right away, see
+    /// [`MainExitPolicy`].
+    pub fn on_main_exit(&mut self, policy: MainExitPolicy) -> &mut Self {
+        self.on_main_exit = policy;
+        self
+    }
+
+    /// Whether and how the main child is restarted when it exits. By
+    /// default it is never restarted. This applies to
+    /// [`Pid1Settings::launch`] and [`Pid1Settings::supervise_command`],
+    /// which know how to spawn the child again.
+    pub fn restart_policy(&mut self, policy: RestartPolicy) -> &mut Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Periodically check the health of the main child, see
+    /// [`HealthCheck`]. By default there is no health check. With
+    /// [`Pid1Settings::supervise_services`], use
+    /// [`Service::health_check`] instead.
+    pub fn health_check(&mut self, check: HealthCheck) -> &mut Self {
+        self.health_check = Some(check);
+        self
+    }
+
+    /// Exit with `to` instead of `from` once the supervision is over,
+    /// for example to report the exit code `143` of a child stopped by
+    /// `SIGTERM` as a success. This only applies to the final exit code,
+    /// see [`SupervisionOutcome::exit_code`]: the
+    /// [`Pid1Settings::restart_policy`] still sees the actual one.
+    pub fn map_exit_code(&mut self, from: i32, to: i32) -> &mut Self {
+        self.exit_code_map.insert(from, to);
+        self
+    }
+
+    /// Exit codes reported as `0`, see [`Pid1Settings::map_exit_code`].
+    pub fn success_exit_codes(&mut self, codes: impl IntoIterator<Item = i32>) -> &mut Self {
+        for code in codes {
+            self.exit_code_map.insert(code, 0);
+        }
+        self
+    }
+
+    /// Signals which are forwarded unchanged to the child process. By
+    /// default all the [`catchable_signals`] are forwarded.
+    ///
+    /// `SIGTERM` and `SIGINT` are always handled: they are delivered
+    /// to the child as part of the graceful shutdown, see
+    /// [`Pid1Settings::shutdown_policy`].
+    pub fn forward_signals(&mut self, signals: impl IntoIterator<Item = c_int>) -> &mut Self {
+        self.forward_signals = signals.into_iter().collect();
+        self
+    }
+
+    /// Deliver `to` to the child process whenever PID 1 receives
+    /// `from`, or drop `from` entirely if `to` is `None`. This also
+    /// applies to `SIGTERM` and `SIGINT`, so for example rewriting
+    /// `SIGTERM` to `SIGQUIT` makes the graceful shutdown start with
+    /// `SIGQUIT`. A rewritten signal is forwarded even if it's not
+    /// part of [`Pid1Settings::forward_signals`].
+    pub fn rewrite_signal(&mut self, from: c_int, to: Option<c_int>) -> &mut Self {
+        self.rewrite_signals.insert(from, to);
+        self
+    }
+
+    /// Spawn the child in its own process group and deliver signals
+    /// to the whole group instead of only the child. This makes sure
+    /// that processes started by a shell wrapper (e.g. the pipeline in
+    /// `sh -c "a | b"`) see the signals too. By default it is 'false'.
+    ///
+    /// When using [`Pid1Settings::pid1_handling`] directly, the child
+    /// has to be spawned with [`std::os::unix::process::CommandExt::process_group`]
+    /// set to `0`, otherwise the signals are only sent to the child.
+    pub fn process_group(&mut self, process_group: bool) -> &mut Self {
+        self.process_group = process_group;
+        self
+    }
+
+    /// Spawn the child in its own process group and make it the
+    /// foreground process group of the terminal on stdin, so that
+    /// `Ctrl-C`, reading from the terminal and full-screen programs
+    /// work as in an interactive shell (e.g. with `docker run -it`).
+    /// PID 1 takes the terminal back once the child has exited. Does
+    /// nothing when stdin is not a terminal. By default it is 'false'.
+    ///
+    /// This applies to [`Pid1Settings::launch`],
+    /// [`Pid1Settings::supervise_command`] and the restarted children,
+    /// but not to [`Pid1Settings::supervise_services`].
+    pub fn tty(&mut self, tty: bool) -> &mut Self {
+        self.tty = tty;
+        self
+    }
+
+    /// Supervise the child and reap orphans even when not running as
+    /// PID 1, by becoming a child subreaper (see
+    /// [`become_child_subreaper`]). This is useful when running with
+    /// `docker run --pid=host`, a shared process namespace in
+    /// Kubernetes or in CI runners. By default it is 'false'.
+    pub fn subreaper(&mut self, subreaper: bool) -> &mut Self {
+        self.subreaper = subreaper;
+        self
+    }
+
+    /// Listen for commands on a Unix socket at `path`, one per
+    /// connection: `status`, `signal NAME`, `restart`, `stop` and `ps`.
+    /// The response starts with `ok`, followed by the output of the
+    /// command, or with `error:` and the reason it failed, such as a
+    /// signal which is not forwarded. A socket left over at `path` is
+    /// replaced, but not any other kind of file, and removed once the
+    /// supervision is over. Only the owner of the process can connect
+    /// to the socket. By default there is no control socket.
+    ///
+    /// `restart` stops the children following the
+    /// [`Pid1Settings::shutdown_policy`] and spawns them again, so it
+    /// fails with [`Pid1Settings::pid1_handling`] which cannot respawn
+    /// the child.
+    pub fn control_socket(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.control_socket = Some(path.into());
+        self
+    }
+
+    /// Invoke the callbacks of `observer` as the child process is
+    /// supervised, for example to collect metrics. Pass an [`Arc`] to
+    /// keep access to the observer afterwards.
+    #[cfg(target_family = "unix")]
+    pub fn observer(&mut self, observer: impl Pid1Observer + 'static) -> &mut Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Adjust the command [`Pid1Settings::launch`] relaunches the
+    /// current executable with before it is spawned, for example to set
+    /// extra environment variables, arguments or stdio. The command
+    /// already has the arguments and `argv[0]` of the current process,
+    /// and the hook may clear its environment. It is also used to
+    /// restart the child, see [`Pid1Settings::restart_policy`].
+    #[cfg(target_family = "unix")]
+    pub fn configure_relaunch(
+        &mut self,
+        hook: impl Fn(&mut std::process::Command) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.relaunch_hook = Some(RelaunchHook(Arc::new(hook)));
+        self
+    }
+
+    /// How the current executable is run again by
+    /// [`Pid1Settings::launch`]. By default it is
+    /// [`RelaunchStrategy::Path`].
+    pub fn relaunch_strategy(&mut self, strategy: RelaunchStrategy) -> &mut Self {
+        self.relaunch_strategy = strategy;
+        self
+    }
+
+    #[cfg(target_family = "unix")]
+    fn observe(&self, callback: impl FnOnce(&dyn Pid1Observer)) {
+        if let Some(observer) = &self.observer {
+            callback(observer.as_ref());
+        }
+    }
+
+    /// The signal to deliver to the child when PID 1 receives
+    /// `signal`, taking [`Pid1Settings::rewrite_signal`] into account.
+    #[cfg(target_family = "unix")]
+    fn rewritten(&self, signal: c_int) -> Option<c_int> {
+        self.rewrite_signals
+            .get(&signal)
+            .copied()
+            .unwrap_or(Some(signal))
+    }
+
+    #[cfg(target_family = "unix")]
+    fn is_forwarded(&self, signal: c_int) -> bool {
+        self.forward_signals.contains(&signal) || self.rewrite_signals.contains_key(&signal)
+    }
+
+    /// Register handlers for all the signals PID 1 is interested in:
+    /// `SIGTERM`, `SIGINT`, `SIGCHLD` and the ones configured with
+    /// [`Pid1Settings::forward_signals`] or
+    /// [`Pid1Settings::rewrite_signal`]. This has to be done before
+    /// the child process is spawned and the result should be passed
+    /// to [`Pid1Settings::pid1_handling`].
+    #[cfg(target_family = "unix")]
+    pub fn register_signals(&self) -> Result<Signals, Error> {
+        Signals::new(self.handled_signals()).map_err(Error::SignalRegistration)
+    }
+
+    #[cfg(target_family = "unix")]
+    fn handled_signals(&self) -> Vec<c_int> {
+        let mut signals = vec![SIGTERM, SIGINT, SIGCHLD];
+        for signal in self
+            .forward_signals
+            .iter()
+            .chain(self.rewrite_signals.keys())
+        {
+            // Uncatchable signals would make signal-hook panic
+            if !signals.contains(signal) && !FORBIDDEN.contains(signal) {
+                signals.push(*signal);
+            }
+        }
+        signals
+    }
+
     #[allow(clippy::needless_doctest_main)]
     /// When run as PID 1, relaunch the current process as a child process
-    /// and do proper signal and zombie reaping in PID 1.
+    /// and do proper signal and zombie reaping in PID 1. With
+    /// [`Pid1Settings::subreaper`] the same happens when not running as
+    /// PID 1.
     ///
     /// This function should be the first statement within your main
     /// function.
@@ -89,33 +457,126 @@ impl Pid1Settings {
     /// Note that this function is only applicable for Unix
     /// systems. For Windows, it will return [`Ok(())`].
     #[cfg(target_family = "unix")]
-    pub fn launch(self) -> Result<(), Error> {
+    pub fn launch(&self) -> Result<(), Error> {
+        if std::env::var_os(RELAUNCHED_ENV).is_some() {
+            // We are the relaunched child
+            std::env::remove_var(RELAUNCHED_ENV);
+            return Ok(());
+        }
         let pid = std::process::id();
-        if pid == 1 {
+        if pid == 1 || self.subreaper {
+            if pid != 1 {
+                become_child_subreaper()?;
+            }
             // Install signal handles before we launch child process
-            let signals = Signals::new([SIGTERM, SIGINT, SIGCHLD]).unwrap();
-            let child = relaunch()?;
-            if self.log {
-                eprintln!("pid1-rs: Process running as PID 1");
+            let signals = self.register_signals()?;
+            // The executable stays open for the child to be restarted
+            let (mut command, _executable) = self.relaunch_command()?;
+            let child = command.spawn().map_err(Error::SpawnChild)?;
+            if pid == 1 {
+                event!(self, Info, "Process running as PID 1");
+            } else {
+                event!(
+                    self,
+                    Info,
+                    { pid = pid },
+                    "Process running as child subreaper: PID {pid}"
+                );
             }
-            pid1_handling(self, signals, child)
+            let mut supervision = Supervision::new(self);
+            let respawn = Box::new(|| command.spawn());
+            supervision.add_child(
+                None,
+                true,
+                Ok(child.id()),
+                Some(respawn),
+                self.health_check.clone(),
+            );
+            std::process::exit(supervise::supervise(supervision, signals).exit_code())
         } else {
             Ok(())
         }
     }
     #[cfg(target_family = "windows")]
-    pub fn launch(self) -> Result<(), Error> {
-        if self.log {
-            eprintln!("pid1-rs: PID1 capability not supported for Windows");
-        }
+    pub fn launch(&self) -> Result<(), Error> {
+        event!(self, Info, "PID1 capability not supported for Windows");
         Ok(())
     }
 
     /// Do proper reaping and signal handling on the [`Child`]
-    /// process. This method is only available for Unix systems.
+    /// process and exit with its exit code. This method is only
+    /// available for Unix systems.
     #[cfg(target_family = "unix")]
-    pub fn pid1_handling(self, signals: Signals, child: Child) -> ! {
-        pid1_handling(self, signals, child)
+    pub fn pid1_handling(&self, signals: Signals, child: Child) -> ! {
+        std::process::exit(self.supervise(signals, child).exit_code())
+    }
+
+    /// Do proper reaping and signal handling on the [`Child`] process
+    /// until it exits, and return how it ended instead of exiting.
+    /// The `signals` should come from [`Pid1Settings::register_signals`].
+    /// This method is only available for Unix systems.
+    #[cfg(target_family = "unix")]
+    pub fn supervise(&self, signals: Signals, child: Child) -> SupervisionOutcome {
+        let mut supervision = Supervision::new(self);
+        supervision.add_child(None, true, Ok(child.id()), None, self.health_check.clone());
+        supervise::supervise(supervision, signals)
+    }
+
+    /// Spawn `command` and supervise it like
+    /// [`Pid1Settings::supervise`]. The child is put in its own process
+    /// group when using [`Pid1Settings::process_group`], and restarted
+    /// according to the [`Pid1Settings::restart_policy`]. If spawning
+    /// fails, the outcome has a [`ChildStatus::SpawnFailed`] status.
+    #[cfg(target_family = "unix")]
+    pub fn supervise_command(
+        &self,
+        signals: Signals,
+        command: &mut std::process::Command,
+    ) -> SupervisionOutcome {
+        if self.process_group {
+            command.process_group(0);
+        }
+        if self.tty {
+            tty::foreground(command);
+        }
+        let mut supervision = Supervision::new(self);
+        let child = command.spawn().map(|child| child.id());
+        let respawn = Box::new(|| command.spawn());
+        supervision.add_child(None, true, child, Some(respawn), self.health_check.clone());
+        supervise::supervise(supervision, signals)
+    }
+
+    /// Spawn all the `services` and supervise them like
+    /// [`Pid1Settings::supervise`]: signals are forwarded to each of
+    /// them, `SIGTERM` and `SIGINT` stop all of them following the
+    /// [`Pid1Settings::shutdown_policy`], and each of them is restarted
+    /// according to the [`Pid1Settings::restart_policy`].
+    ///
+    /// Once a [`Service::critical`] service exits for good, the other
+    /// ones are stopped as if PID 1 received `SIGTERM`. Supervision
+    /// ends once all the services have exited.
+    #[cfg(target_family = "unix")]
+    pub fn supervise_services(
+        &self,
+        signals: Signals,
+        services: &mut [Service],
+    ) -> SupervisionOutcome {
+        let mut supervision = Supervision::new(self);
+        for service in services {
+            if self.process_group {
+                service.command.process_group(0);
+            }
+            let child = service.command.spawn().map(|child| child.id());
+            let command = &mut service.command;
+            supervision.add_child(
+                Some(service.name.clone()),
+                service.critical,
+                child,
+                Some(Box::new(|| command.spawn())),
+                service.health_check.clone(),
+            );
+        }
+        supervise::supervise(supervision, signals)
     }
 }
 
@@ -124,127 +585,97 @@ impl Default for Pid1Settings {
         Self {
             log: Default::default(),
             timeout: Duration::from_secs(2),
+            #[cfg(target_family = "unix")]
+            forward_signals: catchable_signals(),
+            #[cfg(target_family = "windows")]
+            forward_signals: Vec::new(),
+            rewrite_signals: BTreeMap::new(),
+            process_group: false,
+            tty: false,
+            subreaper: false,
+            shutdown_policy: None,
+            on_main_exit: MainExitPolicy::default(),
+            restart_policy: RestartPolicy::default(),
+            health_check: None,
+            control_socket: None,
+            exit_code_map: BTreeMap::new(),
+            #[cfg(target_family = "unix")]
+            observer: None,
+            #[cfg(target_family = "unix")]
+            relaunch_hook: None,
+            relaunch_strategy: RelaunchStrategy::default(),
         }
     }
 }
 
 #[cfg(target_family = "unix")]
-fn relaunch() -> Result<Child, Error> {
-    let exe = std::env::current_exe().unwrap();
-    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
-    std::process::Command::new(exe)
-        .args(args)
-        .spawn()
-        .map_err(Error::SpawnChild)
-}
-
-/// Graceful exit: We dispatch the singal that got to the application,
-/// followed by SIGTERM and SIGKILL.
-#[cfg(target_family = "unix")]
-fn graceful_exit(settings: Pid1Settings, signal: c_int, child_pid: i32) -> Result<(), Error> {
-    if signal == SIGINT {
-        let _ = kill(Pid::from_raw(child_pid), Some(nix::sys::signal::SIGINT));
-        std::thread::sleep(settings.timeout);
+impl Pid1Settings {
+    /// The command to relaunch the current executable with, along with
+    /// the file it runs when using [`RelaunchStrategy::FileDescriptor`].
+    fn relaunch_command(&self) -> Result<(std::process::Command, Option<File>), Error> {
+        let (exe, executable) = match self.relaunch_strategy {
+            RelaunchStrategy::Path => (std::env::current_exe().map_err(Error::CurrentExe)?, None),
+            RelaunchStrategy::FileDescriptor => {
+                // The file is opened with `O_CLOEXEC`, which only closes
+                // it once the kernel has opened it for `execve`, and
+                // `O_PATH`, which runs executables without read access
+                let mut options = OpenOptions::new();
+                options.read(true);
+                #[cfg(target_os = "linux")]
+                options.custom_flags(libc::O_PATH);
+                let executable = options.open("/proc/self/exe").map_err(Error::CurrentExe)?;
+                let path = format!("/proc/self/fd/{}", executable.as_raw_fd());
+                (path.into(), Some(executable))
+            }
+        };
+        let mut args = std::env::args_os();
+        let mut command = std::process::Command::new(exe);
+        // Multi-call binaries dispatch on the name they were run as
+        if let Some(arg0) = args.next() {
+            command.arg0(arg0);
+        }
+        command.args(args);
+        if self.process_group {
+            command.process_group(0);
+        }
+        if self.tty {
+            tty::foreground(&mut command);
+        }
+        if let Some(RelaunchHook(hook)) = &self.relaunch_hook {
+            hook(&mut command);
+        }
+        // After the hook, which may clear the environment: without it
+        // the child would relaunch itself again
+        command.env(RELAUNCHED_ENV, "1");
+        Ok((command, executable))
     }
-    // Send SIGTERM to the child process
-    let _ = kill(Pid::from_raw(child_pid), Some(nix::sys::signal::SIGTERM));
-    std::thread::sleep(settings.timeout);
 
-    // Okay, the child process is still present. Use the SIGKILL card.
-    let _ = kill(Pid::from_raw(child_pid), Some(nix::sys::signal::SIGKILL));
-    Ok(())
-}
-
-#[cfg(target_family = "unix")]
-fn pid1_handling(settings: Pid1Settings, mut signals: Signals, child: Child) -> ! {
-    let child = child.id() as i32;
-    struct ProcessStatus {
-        pid: Pid,
-        exit_code: i32,
+    /// The exit code to report for `exit_code`, taking
+    /// [`Pid1Settings::map_exit_code`] into account.
+    fn mapped_exit_code(&self, exit_code: i32) -> i32 {
+        self.exit_code_map
+            .get(&exit_code)
+            .copied()
+            .unwrap_or(exit_code)
     }
 
-    enum ShutdownThreadStatus {
-        Triggered,
-        NotTriggered,
+    fn effective_shutdown_policy(&self) -> ShutdownPolicy {
+        self.shutdown_policy
+            .clone()
+            .unwrap_or_else(|| ShutdownPolicy::with_timeout(self.timeout))
     }
 
-    let mut shutdown_thread = ShutdownThreadStatus::NotTriggered;
-
-    loop {
-        for signal in signals.forever() {
-            if signal == SIGTERM || signal == SIGINT {
-                // We do graceful exit in a separate thread so that
-                // pid1 exits as soon as possible
-                if let ShutdownThreadStatus::NotTriggered = shutdown_thread {
-                    shutdown_thread = ShutdownThreadStatus::Triggered;
-                    let _ = std::thread::spawn(move || graceful_exit(settings, signal, child));
-                }
-                // We do not exit here since we want the SIGCHLD
-                // handler to be invoked appropriately.
-            }
-            if signal == SIGCHLD {
-                let mut main_child_exit_code = None;
-                // Multiple child processes can exit in quick succession, but the
-                // operating system may only deliver a single SIGCHLD signal.
-                // This is known as signal coalescing. To handle this, we loop
-                // with a non-blocking `waitpid` call to reap all zombies.
-                // Using a blocking `wait` would hang if there are no more
-                // children to reap, preventing us from handling other signals.
-                // Reference: https://stackoverflow.com/a/8398491/1651941
-                loop {
-                    let wait_status = match nix::sys::wait::waitpid(
-                        None,
-                        Some(nix::sys::wait::WaitPidFlag::WNOHANG),
-                    ) {
-                        Ok(status) => status,
-                        Err(nix::errno::Errno::ECHILD) => {
-                            // No more children to wait for
-                            break;
-                        }
-                        Err(e) => {
-                            if settings.log {
-                                eprintln!("pid1-rs: Error in waitpid: {e}");
-                            }
-                            break;
-                        }
-                    };
-
-                    let child_process_status = match wait_status {
-                        WaitStatus::Exited(pid, exit_code) => {
-                            Some(ProcessStatus { pid, exit_code })
-                        }
-                        WaitStatus::Signaled(pid, signal, _) => {
-                            // Translate signal to exit code
-                            let exit_code = signal as i32 + 128;
-                            Some(ProcessStatus { pid, exit_code })
-                        }
-                        WaitStatus::StillAlive => {
-                            // No more children to reap now
-                            break;
-                        }
-                        WaitStatus::Stopped(..) => None,
-                        #[cfg(any(target_os = "linux", target_os = "android"))]
-                        WaitStatus::PtraceEvent(..) | WaitStatus::PtraceSyscall(..) => None,
-                        WaitStatus::Continued(..) => None,
-                    };
-
-                    if let Some(child_process) = child_process_status {
-                        let child_pid = child_process.pid.as_raw();
-                        if child_pid == child {
-                            // Main child has exited. We'll exit with its status code,
-                            // but only after reaping any other children that may have
-                            // exited in this same signal batch.
-                            main_child_exit_code = Some(child_process.exit_code);
-                        }
-                        if settings.log {
-                            eprintln!("pid1-rs: Reaped PID {child_pid}");
-                        }
-                    }
-                }
-                if let Some(exit_code) = main_child_exit_code {
-                    std::process::exit(exit_code);
-                }
-            }
+    /// The PID signals for the child are sent to: the negated process
+    /// group ID when using [`Pid1Settings::process_group`], as long as
+    /// the child leads its own process group. A child spawned without
+    /// one, for example for [`Pid1Settings::pid1_handling`], is sent
+    /// the signals alone rather than not at all.
+    fn signal_target(&self, child_pid: i32) -> i32 {
+        let pid = nix::unistd::Pid::from_raw(child_pid);
+        if self.process_group && nix::unistd::getpgid(Some(pid)) == Ok(pid) {
+            -child_pid
+        } else {
+            child_pid
         }
     }
 }
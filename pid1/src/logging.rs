@@ -0,0 +1,77 @@
+//! Diagnostics emitted by the crate.
+//!
+//! With the `tracing` feature, diagnostics are [tracing] events and
+//! with the `log` feature they are [log] records, both with the
+//! `pid1` target and structured fields such as `pid`, `signal` and
+//! `exit_code`. Otherwise they are printed to stderr when
+//! [`crate::Pid1Settings::enable_log`] is set.
+
+/// Emit a diagnostic at the given level (`Error`, `Warn`, `Info`,
+/// `Debug` or `Trace`), with optional structured fields:
+///
+/// ```ignore
+/// event!(settings, Info, { pid = pid }, "Reaped PID {pid}");
+/// ```
+macro_rules! event {
+    ($settings:expr, $level:ident, { $($key:ident = $value:expr),+ $(,)? }, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        {
+            let _ = &$settings;
+            ::tracing::event!(
+                target: "pid1",
+                $crate::logging::tracing_level!($level),
+                $($key = $value,)+
+                $($arg)+
+            );
+        }
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        {
+            let _ = &$settings;
+            ::log::log!(target: "pid1", ::log::Level::$level, $($key = $value),+; $($arg)+);
+        }
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        {
+            $(let _ = &$value;)+
+            $crate::logging::event!($settings, $level, $($arg)+);
+        }
+    }};
+    ($settings:expr, $level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        {
+            let _ = &$settings;
+            ::tracing::event!(target: "pid1", $crate::logging::tracing_level!($level), $($arg)+);
+        }
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        {
+            let _ = &$settings;
+            ::log::log!(target: "pid1", ::log::Level::$level, $($arg)+);
+        }
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        if $settings.log {
+            eprintln!("pid1-rs: {}", format_args!($($arg)+));
+        }
+    }};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! tracing_level {
+    (Error) => {
+        ::tracing::Level::ERROR
+    };
+    (Warn) => {
+        ::tracing::Level::WARN
+    };
+    (Info) => {
+        ::tracing::Level::INFO
+    };
+    (Debug) => {
+        ::tracing::Level::DEBUG
+    };
+    (Trace) => {
+        ::tracing::Level::TRACE
+    };
+}
+
+pub(crate) use event;
+#[cfg(feature = "tracing")]
+pub(crate) use tracing_level;
@@ -0,0 +1,162 @@
+use crate::{signal::signal_name, Pid1Observer, ReapedProcess};
+use std::{
+    collections::BTreeMap,
+    ffi::c_int,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Counters about the supervision, kept up to date by setting them as
+/// the [`crate::Pid1Settings::observer`]. They render in the Prometheus
+/// text exposition format with [`fmt::Display`].
+///
+/// ```
+/// use pid1::{Metrics, Pid1Settings};
+/// use std::sync::Arc;
+///
+/// let metrics = Arc::new(Metrics::new());
+/// let mut settings = Pid1Settings::new();
+/// settings.observer(metrics.clone());
+/// // Serve `metrics.to_string()` from another thread
+/// assert!(metrics.to_string().contains("pid1_orphans_reaped_total 0"));
+/// ```
+#[derive(Debug, Default)]
+pub struct Metrics {
+    processes_reaped: AtomicU64,
+    orphans_reaped: AtomicU64,
+    restarts: AtomicU64,
+    signals_received: Mutex<BTreeMap<c_int, u64>>,
+    signals_forwarded: AtomicU64,
+    shutdown: Mutex<Shutdown>,
+}
+
+#[derive(Debug, Default)]
+struct Shutdown {
+    started: Option<Instant>,
+    duration: Option<Duration>,
+}
+
+impl Metrics {
+    /// All the counters at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of processes reaped, including the orphans.
+    pub fn processes_reaped(&self) -> u64 {
+        self.processes_reaped.load(Ordering::Relaxed)
+    }
+
+    /// Number of orphans adopted and reaped.
+    pub fn orphans_reaped(&self) -> u64 {
+        self.orphans_reaped.load(Ordering::Relaxed)
+    }
+
+    /// Number of times a child was restarted.
+    pub fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    /// Number of times `signal` was received, before any rewriting.
+    pub fn signals_received(&self, signal: c_int) -> u64 {
+        let received = self.signals_received.lock().unwrap();
+        received.get(&signal).copied().unwrap_or_default()
+    }
+
+    /// Time spent stopping the children so far, or until they all
+    /// exited. `None` if no shutdown was started.
+    pub fn shutdown_duration(&self) -> Option<Duration> {
+        let shutdown = self.shutdown.lock().unwrap();
+        shutdown
+            .duration
+            .or_else(|| shutdown.started.map(|started| started.elapsed()))
+    }
+}
+
+impl Pid1Observer for Metrics {
+    fn signal_received(&self, signal: c_int) {
+        *self
+            .signals_received
+            .lock()
+            .unwrap()
+            .entry(signal)
+            .or_default() += 1;
+    }
+
+    fn signal_forwarded(&self, _signal: c_int, _pid: i32) {
+        self.signals_forwarded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn shutdown_started(&self, _signal: c_int) {
+        self.shutdown.lock().unwrap().started = Some(Instant::now());
+    }
+
+    fn process_reaped(&self, process: &ReapedProcess) {
+        self.processes_reaped.fetch_add(1, Ordering::Relaxed);
+        if process.orphan {
+            self.orphans_reaped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn process_restarted(&self, _pid: i32) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn shutdown_finished(&self, elapsed: Duration) {
+        self.shutdown.lock().unwrap().duration = Some(elapsed);
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counters = [
+            (
+                "pid1_processes_reaped_total",
+                "Processes reaped, including orphans.",
+                self.processes_reaped(),
+            ),
+            (
+                "pid1_orphans_reaped_total",
+                "Orphaned processes adopted and reaped.",
+                self.orphans_reaped(),
+            ),
+            (
+                "pid1_restarts_total",
+                "Restarts of the supervised children.",
+                self.restarts(),
+            ),
+            (
+                "pid1_signals_forwarded_total",
+                "Signals forwarded to the children.",
+                self.signals_forwarded.load(Ordering::Relaxed),
+            ),
+        ];
+        for (name, help, value) in counters {
+            header(f, name, "counter", help)?;
+            writeln!(f, "{name} {value}")?;
+        }
+        let name = "pid1_signals_received_total";
+        header(f, name, "counter", "Signals received, before rewriting.")?;
+        for (signal, count) in self.signals_received.lock().unwrap().iter() {
+            writeln!(f, "{name}{{signal=\"{}\"}} {count}", signal_name(*signal))?;
+        }
+        let name = "pid1_shutdown_duration_seconds";
+        header(
+            f,
+            name,
+            "gauge",
+            "Time spent stopping the children so far, or until they all exited.",
+        )?;
+        let duration = self.shutdown_duration().unwrap_or_default();
+        writeln!(f, "{name} {}", duration.as_secs_f64())
+    }
+}
+
+fn header(f: &mut fmt::Formatter<'_>, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(f, "# HELP {name} {help}")?;
+    writeln!(f, "# TYPE {name} {kind}")
+}
@@ -0,0 +1,113 @@
+use crate::{ChildStatus, ReapedProcess};
+use std::{ffi::c_int, fmt, sync::Arc, time::Duration};
+
+/// Callbacks invoked while PID 1 supervises the child process, see
+/// [`crate::Pid1Settings::observer`]. All the methods do nothing by
+/// default, so only the events of interest need to be implemented.
+///
+/// The callbacks are invoked from the supervision loop and should
+/// return quickly: signals are not handled and processes are not
+/// reaped while they run.
+///
+/// ```
+/// use pid1::{Pid1Observer, ReapedProcess};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[derive(Default)]
+/// struct ReapCounter(AtomicUsize);
+///
+/// impl Pid1Observer for ReapCounter {
+///     fn process_reaped(&self, _process: &ReapedProcess) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+/// ```
+pub trait Pid1Observer: Send + Sync {
+    /// PID 1 received `signal`, before any rewriting.
+    fn signal_received(&self, signal: c_int) {
+        let _ = signal;
+    }
+
+    /// `signal` was delivered to `pid`, the child process or its
+    /// process group (as a negative number).
+    fn signal_forwarded(&self, signal: c_int, pid: i32) {
+        let _ = (signal, pid);
+    }
+
+    /// The running children are being stopped with the
+    /// [`crate::ShutdownPolicy`], because PID 1 received `SIGTERM` or
+    /// `SIGINT` (`signal` is the rewritten one) or a critical child
+    /// exited.
+    fn shutdown_started(&self, signal: c_int) {
+        let _ = signal;
+    }
+
+    /// Step number `step` (starting at 1) of the
+    /// [`crate::ShutdownPolicy`] was taken, sending `signal` to `pid`.
+    fn shutdown_step(&self, step: usize, signal: c_int, pid: i32) {
+        let _ = (step, signal, pid);
+    }
+
+    /// A process was reaped. This is invoked for the main child as
+    /// well as for orphans, see [`ReapedProcess::orphan`].
+    fn process_reaped(&self, process: &ReapedProcess) {
+        let _ = process;
+    }
+
+    /// The main child process exited with `status`. When supervising
+    /// several [`crate::Service`]s, this is invoked for each of them.
+    fn main_exited(&self, status: &ChildStatus) {
+        let _ = status;
+    }
+
+    /// A child exited and was restarted as `pid`, see
+    /// [`crate::RestartPolicy`].
+    fn process_restarted(&self, pid: i32) {
+        let _ = pid;
+    }
+
+    /// All the children exited, `elapsed` after the shutdown started.
+    fn shutdown_finished(&self, elapsed: Duration) {
+        let _ = elapsed;
+    }
+}
+
+impl<T: Pid1Observer + ?Sized> Pid1Observer for Arc<T> {
+    fn signal_received(&self, signal: c_int) {
+        (**self).signal_received(signal)
+    }
+
+    fn signal_forwarded(&self, signal: c_int, pid: i32) {
+        (**self).signal_forwarded(signal, pid)
+    }
+
+    fn shutdown_started(&self, signal: c_int) {
+        (**self).shutdown_started(signal)
+    }
+
+    fn shutdown_step(&self, step: usize, signal: c_int, pid: i32) {
+        (**self).shutdown_step(step, signal, pid)
+    }
+
+    fn process_reaped(&self, process: &ReapedProcess) {
+        (**self).process_reaped(process)
+    }
+
+    fn main_exited(&self, status: &ChildStatus) {
+        (**self).main_exited(status)
+    }
+
+    fn process_restarted(&self, pid: i32) {
+        (**self).process_restarted(pid)
+    }
+
+    fn shutdown_finished(&self, elapsed: Duration) {
+        (**self).shutdown_finished(elapsed)
+    }
+}
+
+impl fmt::Debug for dyn Pid1Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Pid1Observer")
+    }
+}
@@ -0,0 +1,39 @@
+/// The `ParseError` is returned when parsing a
+/// [`crate::ShutdownPolicy`], [`crate::MainExitPolicy`],
+/// [`crate::Restart`], [`crate::HealthProbe`] or
+/// [`crate::UnhealthyAction`] from a string fails.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid `{value}`: {reason}")]
+pub struct ParseError {
+    pub(crate) value: String,
+    pub(crate) reason: &'static str,
+}
+
+/// Split a command line into words on whitespace, honoring single and
+/// double quotes as well as backslash escapes, so that
+/// `sh -c "exit 1"` gives `sh`, `-c` and `exit 1`. Returns `None` if a
+/// quote is not closed.
+pub fn split_command(s: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => word.get_or_insert_with(String::new).push(chars.next()?),
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    words.extend(word);
+    Some(words)
+}
@@ -0,0 +1,201 @@
+#[cfg(target_family = "unix")]
+use std::{
+    collections::{hash_map::RandomState, VecDeque},
+    hash::{BuildHasher, Hasher},
+    time::Instant,
+};
+use std::{str::FromStr, time::Duration};
+
+use crate::ParseError;
+
+/// When the main child process is restarted after it exits, see
+/// [`RestartPolicy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Restart {
+    /// Never restart: PID 1 exits along with the child.
+    #[default]
+    Never,
+    /// Restart the child when it exits with a non-zero exit code or is
+    /// killed by a signal.
+    OnFailure,
+    /// Always restart the child, even when it exits successfully.
+    Always,
+}
+
+/// Parses `never`, `on-failure` or `always`.
+impl FromStr for Restart {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "never" => Ok(Restart::Never),
+            "on-failure" => Ok(Restart::OnFailure),
+            "always" => Ok(Restart::Always),
+            _ => Err(ParseError {
+                value: s.to_owned(),
+                reason: "expected `never`, `on-failure` or `always`",
+            }),
+        }
+    }
+}
+
+/// How the main child process is restarted after it exits. Restarts
+/// are delayed with an exponential backoff: the first one happens after
+/// the initial delay, which is then doubled for every restart within
+/// the window, up to the maximum delay. A random jitter is added on top
+/// so that containers crashing together don't restart in lockstep.
+///
+/// Restarts older than the window are forgotten, which resets the
+/// backoff. When the child is restarted too many times within the
+/// window, it is considered to be crash looping and PID 1 gives up,
+/// exiting with the status of the last failure.
+///
+/// The child is never restarted once PID 1 has been asked to shut down
+/// with `SIGTERM` or `SIGINT`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use pid1::{Restart, RestartPolicy};
+///
+/// let policy = RestartPolicy::new(Restart::OnFailure)
+///     .backoff(Duration::from_millis(500), Duration::from_secs(10))
+///     .max_restarts(5, Duration::from_secs(60));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestartPolicy {
+    restart: Restart,
+    initial_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    max_restarts: Option<u32>,
+    window: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::new(Restart::Never)
+    }
+}
+
+impl RestartPolicy {
+    /// A policy restarting the child according to `restart`, with an
+    /// initial delay of 1 second, a maximum delay of 30 seconds, a
+    /// jitter of 10%, a window of 60 seconds and no limit on the number
+    /// of restarts.
+    pub fn new(restart: Restart) -> Self {
+        Self {
+            restart,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.1,
+            max_restarts: None,
+            window: Duration::from_secs(60),
+        }
+    }
+
+    /// Delay before the first restart, and the maximum delay the
+    /// exponential backoff grows to.
+    pub fn backoff(mut self, initial_delay: Duration, max_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay.max(initial_delay);
+        self
+    }
+
+    /// Random extra delay added to each restart, as a fraction of the
+    /// delay. It is clamped between `0.0` and `1.0`.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Give up once the child has been restarted `count` times within
+    /// `window`.
+    pub fn max_restarts(mut self, count: u32, window: Duration) -> Self {
+        self.max_restarts = Some(count);
+        self.window = window;
+        self
+    }
+
+    /// Time after which a restart is forgotten, resetting the backoff.
+    /// This is also the window used by
+    /// [`RestartPolicy::max_restarts`].
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// When the child is restarted.
+    pub fn restart(&self) -> Restart {
+        self.restart
+    }
+}
+
+/// Why the main child is not restarted.
+#[cfg(target_family = "unix")]
+pub(crate) enum NoRestart {
+    /// The policy doesn't restart the child after this exit.
+    Policy,
+    /// The child was restarted too many times within the window.
+    CrashLoop { count: u32, window: Duration },
+}
+
+/// Tracks the restarts of the main child, to compute the backoff and
+/// detect crash loops.
+#[cfg(target_family = "unix")]
+pub(crate) struct Restarts {
+    policy: RestartPolicy,
+    history: VecDeque<Instant>,
+}
+
+#[cfg(target_family = "unix")]
+impl Restarts {
+    pub(crate) fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Record that the main child exited, successfully or not, and
+    /// return how long to wait before restarting it.
+    pub(crate) fn next_delay(&mut self, failed: bool) -> Result<Duration, NoRestart> {
+        match self.policy.restart {
+            Restart::Never => return Err(NoRestart::Policy),
+            Restart::OnFailure if !failed => return Err(NoRestart::Policy),
+            Restart::OnFailure | Restart::Always => {}
+        }
+        let now = Instant::now();
+        while let Some(restart) = self.history.front() {
+            if now.duration_since(*restart) < self.policy.window {
+                break;
+            }
+            self.history.pop_front();
+        }
+        let recent = self.history.len() as u32;
+        if let Some(count) = self.policy.max_restarts {
+            if recent >= count {
+                return Err(NoRestart::CrashLoop {
+                    count,
+                    window: self.policy.window,
+                });
+            }
+        }
+        let delay = self
+            .policy
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(recent))
+            .min(self.policy.max_delay);
+        let delay = delay.mul_f64(1.0 + self.policy.jitter * random_fraction());
+        self.history.push_back(now + delay);
+        Ok(delay)
+    }
+}
+
+/// A random number between 0 and 1, good enough for jitter.
+#[cfg(target_family = "unix")]
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,69 @@
+use crate::HealthCheck;
+use std::process::Command;
+
+/// A process supervised along with others by
+/// [`crate::Pid1Settings::supervise_services`]. Its environment,
+/// working directory and user are the ones of its [`Command`].
+///
+/// # Example
+///
+/// ```rust
+/// use std::process::Command;
+/// use pid1::Service;
+///
+/// let mut shipper = Command::new("fluent-bit");
+/// shipper.env("FLB_LOG_LEVEL", "warn");
+/// let services = [
+///     Service::new("app", Command::new("my-server")),
+///     Service::new("shipper", shipper).critical(false),
+/// ];
+/// ```
+#[derive(Debug)]
+pub struct Service {
+    pub(crate) name: String,
+    pub(crate) command: Command,
+    pub(crate) critical: bool,
+    pub(crate) health_check: Option<HealthCheck>,
+}
+
+impl Service {
+    /// A critical service named `name`, running `command`.
+    pub fn new(name: impl Into<String>, command: Command) -> Self {
+        Self {
+            name: name.into(),
+            command,
+            critical: true,
+            health_check: None,
+        }
+    }
+
+    /// Whether the exit of this service brings down all the others,
+    /// once it is not restarted anymore. By default it is 'true'.
+    pub fn critical(mut self, critical: bool) -> Self {
+        self.critical = critical;
+        self
+    }
+
+    /// Periodically check the health of this service, see
+    /// [`HealthCheck`]. By default there is no health check.
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.health_check = Some(check);
+        self
+    }
+
+    /// The name of this service, used in the logs.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The command run by this service.
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Mutable access to the command run by this service, for example
+    /// to set its environment.
+    pub fn command_mut(&mut self) -> &mut Command {
+        &mut self.command
+    }
+}
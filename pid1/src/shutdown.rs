@@ -0,0 +1,206 @@
+#[cfg(target_family = "unix")]
+use std::time::Instant;
+use std::{ffi::c_int, str::FromStr, time::Duration};
+
+use crate::ParseError;
+
+/// A single step of a [`ShutdownPolicy`]: send a signal to the child
+/// process and wait for it to exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownStep {
+    /// Signal to send. `None` stands for the signal which triggered
+    /// the shutdown, after [`crate::Pid1Settings::rewrite_signal`].
+    pub signal: Option<c_int>,
+    /// How long to wait for the child process to exit before moving
+    /// on to the next step.
+    pub wait: Duration,
+}
+
+/// Ordered list of steps taken to stop the child process once PID 1
+/// receives `SIGTERM` or `SIGINT`. As soon as the child has exited,
+/// the remaining steps are skipped.
+///
+/// The default policy sends the received signal, waits for
+/// [`crate::Pid1Settings::timeout`] and then sends `SIGKILL`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use pid1::ShutdownPolicy;
+/// use signal_hook::consts::{SIGINT, SIGKILL, SIGTERM};
+///
+/// let policy = ShutdownPolicy::new()
+///     .step(SIGTERM, Duration::from_secs(10))
+///     .step(SIGINT, Duration::from_secs(5))
+///     .step(SIGKILL, Duration::ZERO);
+/// # #[cfg(target_family = "unix")]
+/// assert_eq!(policy, "TERM:10s,INT:5s,KILL".parse().unwrap());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownPolicy {
+    steps: Vec<ShutdownStep>,
+}
+
+impl ShutdownPolicy {
+    /// An empty policy. Add steps with [`ShutdownPolicy::step`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The policy used when none is configured: forward the received
+    /// signal, wait for `timeout` and send `SIGKILL`.
+    #[cfg(target_family = "unix")]
+    pub(crate) fn with_timeout(timeout: Duration) -> Self {
+        Self::new()
+            .forward_received(timeout)
+            .step(libc::SIGKILL, Duration::ZERO)
+    }
+
+    /// Append a step sending `signal` and waiting up to `wait` for the
+    /// child process to exit.
+    pub fn step(mut self, signal: c_int, wait: Duration) -> Self {
+        self.steps.push(ShutdownStep {
+            signal: Some(signal),
+            wait,
+        });
+        self
+    }
+
+    /// Append a step sending the signal which triggered the shutdown
+    /// and waiting up to `wait` for the child process to exit.
+    pub fn forward_received(mut self, wait: Duration) -> Self {
+        self.steps.push(ShutdownStep { signal: None, wait });
+        self
+    }
+
+    /// The steps of this policy.
+    pub fn steps(&self) -> &[ShutdownStep] {
+        &self.steps
+    }
+}
+
+/// Parses a comma separated list of `SIGNAL[:WAIT]` steps, such as
+/// `TERM:10s,INT:5s,KILL`. `WAIT` is parsed with [`parse_duration`]
+/// and defaults to zero. The signal `RECEIVED` stands for the signal
+/// which triggered the shutdown.
+#[cfg(target_family = "unix")]
+impl FromStr for ShutdownPolicy {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = ShutdownPolicy::new();
+        for step in s.split(',') {
+            let error = |reason| ParseError {
+                value: step.to_owned(),
+                reason,
+            };
+            let (signal, wait) = match step.split_once(':') {
+                Some((signal, wait)) => (
+                    signal,
+                    parse_duration(wait).ok_or_else(|| error("invalid duration"))?,
+                ),
+                None => (step, Duration::ZERO),
+            };
+            policy = if signal.trim().eq_ignore_ascii_case("RECEIVED") {
+                policy.forward_received(wait)
+            } else {
+                let signal = crate::parse_signal(signal).ok_or_else(|| error("unknown signal"))?;
+                policy.step(signal, wait)
+            };
+        }
+        Ok(policy)
+    }
+}
+
+/// Parse a duration such as `500ms`, `10s`, `2m` or `1h`. A plain
+/// number is interpreted as seconds.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value = value.parse::<u64>().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(value)),
+        "" | "s" => Some(Duration::from_secs(value)),
+        "m" => value.checked_mul(60).map(Duration::from_secs),
+        "h" => value.checked_mul(60 * 60).map(Duration::from_secs),
+        _ => None,
+    }
+}
+
+/// What PID 1 does once the main child process has exited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MainExitPolicy {
+    /// Exit right away with the status of the main child. Remaining
+    /// processes are killed by the kernel when PID 1 exits.
+    #[default]
+    Exit,
+    /// Send `SIGTERM` to all the remaining processes and keep reaping
+    /// them for up to the given duration. Whatever is left after that
+    /// gets `SIGKILL`. PID 1 then exits with the status of the main
+    /// child.
+    TerminateDescendants(Duration),
+}
+
+/// Parses `exit` or `terminate[:WAIT]`, where `WAIT` is parsed with
+/// [`parse_duration`] and defaults to 2 seconds.
+impl FromStr for MainExitPolicy {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason| ParseError {
+            value: s.to_owned(),
+            reason,
+        };
+        let (policy, wait) = match s.split_once(':') {
+            Some((policy, wait)) => (policy, Some(wait)),
+            None => (s, None),
+        };
+        match (policy.trim().to_ascii_lowercase().as_str(), wait) {
+            ("exit", None) => Ok(MainExitPolicy::Exit),
+            ("terminate", None) => Ok(MainExitPolicy::TerminateDescendants(Duration::from_secs(2))),
+            ("terminate", Some(wait)) => parse_duration(wait)
+                .map(MainExitPolicy::TerminateDescendants)
+                .ok_or_else(|| error("invalid duration")),
+            _ => Err(error("expected `exit` or `terminate[:WAIT]`")),
+        }
+    }
+}
+
+/// Progress of a [`ShutdownPolicy`] being carried out.
+#[cfg(target_family = "unix")]
+pub(crate) struct Escalation {
+    policy: ShutdownPolicy,
+    received: c_int,
+    next_step: usize,
+    deadline: Instant,
+}
+
+#[cfg(target_family = "unix")]
+impl Escalation {
+    pub(crate) fn new(policy: ShutdownPolicy, received: c_int) -> Self {
+        Self {
+            policy,
+            received,
+            next_step: 0,
+            deadline: Instant::now(),
+        }
+    }
+
+    /// Take all the steps which are due, calling `send` with the
+    /// signal and index of each. Returns when the next step is due, if
+    /// there is any.
+    pub(crate) fn advance(&mut self, mut send: impl FnMut(c_int, usize)) -> Option<Instant> {
+        loop {
+            let now = Instant::now();
+            if now < self.deadline {
+                return Some(self.deadline);
+            }
+            let step = self.policy.steps.get(self.next_step)?;
+            send(step.signal.unwrap_or(self.received), self.next_step);
+            self.next_step += 1;
+            self.deadline = now + step.wait;
+        }
+    }
+}
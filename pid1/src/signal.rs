@@ -0,0 +1,245 @@
+use nix::{errno::Errno, sys::signal::Signal};
+use std::ffi::c_int;
+#[cfg(all(target_os = "linux", feature = "epoll"))]
+use std::os::fd::{AsFd, BorrowedFd};
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// Parse a signal from its name or number. Names are case
+/// insensitive and the `SIG` prefix is optional, so `TERM`, `sigterm`
+/// and `15` all refer to `SIGTERM`. On Linux, real-time signals can
+/// be given as `RTMIN+N` or `RTMAX-N`.
+pub fn parse_signal(name: &str) -> Option<c_int> {
+    let name = name.trim();
+    if let Ok(number) = name.parse::<c_int>() {
+        return is_valid_signal(number).then_some(number);
+    }
+    let name = name.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    #[cfg(target_os = "linux")]
+    if let Some(signal) = parse_realtime_signal(name) {
+        return Some(signal);
+    }
+    format!("SIG{name}")
+        .parse::<Signal>()
+        .ok()
+        .map(|signal| signal as c_int)
+}
+
+/// Human readable name of a signal, such as `SIGTERM` or
+/// `SIGRTMIN+3`. Unknown signals are rendered as their number.
+pub fn signal_name(signal: c_int) -> String {
+    if let Ok(signal) = Signal::try_from(signal) {
+        return signal.as_str().to_owned();
+    }
+    #[cfg(target_os = "linux")]
+    if (libc::SIGRTMIN()..=libc::SIGRTMAX()).contains(&signal) {
+        return format!("SIGRTMIN+{}", signal - libc::SIGRTMIN());
+    }
+    signal.to_string()
+}
+
+/// All the signals which can be caught and forwarded to the child
+/// process. Similar to `tini`, this leaves out the signals that
+/// cannot be caught (`SIGKILL`, `SIGSTOP`), the ones raised
+/// synchronously by faults in PID 1 itself, `SIGCHLD` (used for
+/// reaping) and `SIGPIPE`. The job control signals (`SIGTSTP`,
+/// `SIGTTIN`, `SIGTTOU` and `SIGCONT`) are included, so that the child
+/// can be suspended and resumed.
+pub fn catchable_signals() -> Vec<c_int> {
+    use Signal::*;
+    let excluded = [
+        SIGKILL, SIGSTOP, SIGCHLD, SIGILL, SIGFPE, SIGSEGV, SIGBUS, SIGABRT, SIGTRAP, SIGSYS,
+        SIGPIPE,
+    ];
+    #[allow(unused_mut)]
+    let mut signals = Signal::iterator()
+        .filter(|signal| !excluded.contains(signal))
+        .map(|signal| signal as c_int)
+        .collect::<Vec<_>>();
+    #[cfg(target_os = "linux")]
+    signals.extend(libc::SIGRTMIN()..=libc::SIGRTMAX());
+    signals
+}
+
+/// Send a raw signal number to `pid`. Unlike [`nix::sys::signal::kill`]
+/// this also works for real-time signals.
+pub(crate) fn send_signal(pid: i32, signal: c_int) -> Result<(), Errno> {
+    Errno::result(unsafe { libc::kill(pid, signal) }).map(drop)
+}
+
+/// A child process of PID 1. On Linux, it is tracked with a pidfd
+/// (see `pidfd_open(2)`) when the kernel supports it, so that signals
+/// can never reach another process which reused its PID, and its exit
+/// can be polled for.
+pub(crate) struct ChildProcess {
+    pid: i32,
+    #[cfg(target_os = "linux")]
+    pidfd: Option<OwnedFd>,
+}
+
+impl ChildProcess {
+    /// Track the child `pid`, which must not have been reaped yet.
+    /// Without pidfd support (before Linux 5.3, or when a seccomp
+    /// profile forbids it), the child is tracked by PID only.
+    pub(crate) fn new(pid: i32) -> Self {
+        #[cfg(target_os = "linux")]
+        let pidfd = {
+            let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+            (fd >= 0).then(|| unsafe { OwnedFd::from_raw_fd(fd as c_int) })
+        };
+        Self {
+            pid,
+            #[cfg(target_os = "linux")]
+            pidfd,
+        }
+    }
+
+    pub(crate) fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// A descriptor which becomes readable once the child exits, if
+    /// the child is tracked with a pidfd.
+    #[cfg(all(target_os = "linux", feature = "epoll"))]
+    pub(crate) fn pidfd(&self) -> Option<BorrowedFd<'_>> {
+        self.pidfd.as_ref().map(|pidfd| pidfd.as_fd())
+    }
+
+    /// Send `signal` to the child, through its pidfd if there is one.
+    pub(crate) fn send_signal(&self, signal: c_int) -> Result<(), Errno> {
+        #[cfg(target_os = "linux")]
+        if let Some(pidfd) = &self.pidfd {
+            let result = Errno::result(unsafe {
+                libc::syscall(
+                    libc::SYS_pidfd_send_signal,
+                    pidfd.as_raw_fd(),
+                    signal,
+                    std::ptr::null::<libc::siginfo_t>(),
+                    0,
+                )
+            });
+            // `pidfd_send_signal` predates `pidfd_open`, but it may
+            // still be forbidden on its own
+            if result != Err(Errno::ENOSYS) && result != Err(Errno::EPERM) {
+                return result.map(drop);
+            }
+        }
+        send_signal(self.pid, signal)
+    }
+}
+
+/// Wakes up the supervision loop from another thread by sending it
+/// `SIGCHLD`, which is idempotent: a spurious one just results in a
+/// reap pass finding nothing.
+#[derive(Clone, Copy)]
+pub(crate) struct Waker {
+    #[cfg(target_os = "linux")]
+    tid: libc::pid_t,
+}
+
+impl Waker {
+    /// A waker for the calling thread, which runs the supervision loop.
+    pub(crate) fn current() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            tid: unsafe { libc::gettid() },
+        }
+    }
+
+    pub(crate) fn wake(&self) {
+        // The signal is directed at the supervision thread, since a
+        // signalfd only sees the signals of the thread reading it
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::syscall(libc::SYS_tgkill, libc::getpid(), self.tid, libc::SIGCHLD);
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = send_signal(std::process::id() as i32, libc::SIGCHLD);
+    }
+}
+
+/// Send `signal` to all the processes PID 1 is responsible for. When
+/// running as PID 1 this is every other process in the PID namespace,
+/// otherwise (as a child subreaper) all of our descendants.
+pub(crate) fn signal_descendants(signal: c_int) {
+    if std::process::id() == 1 {
+        let _ = send_signal(-1, signal);
+    } else {
+        for pid in descendants() {
+            let _ = send_signal(pid, signal);
+        }
+    }
+}
+
+/// All the descendants of the current process, found by walking
+/// `/proc`. Empty when `/proc` is not available.
+pub(crate) fn descendants() -> Vec<i32> {
+    let mut parents = Vec::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|pid| pid.parse::<i32>().ok())
+            else {
+                continue;
+            };
+            // The parent PID is the second field after the command
+            // name, which is in parenthesis and may contain spaces.
+            let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+                continue;
+            };
+            let ppid = stat
+                .rsplit_once(')')
+                .and_then(|(_, rest)| rest.split_whitespace().nth(1))
+                .and_then(|ppid| ppid.parse::<i32>().ok());
+            if let Some(ppid) = ppid {
+                parents.push((pid, ppid));
+            }
+        }
+    }
+    let mut descendants = vec![std::process::id() as i32];
+    let mut index = 0;
+    while let Some(&parent) = descendants.get(index) {
+        descendants.extend(
+            parents
+                .iter()
+                .filter(|(_, ppid)| *ppid == parent)
+                .map(|(pid, _)| *pid),
+        );
+        index += 1;
+    }
+    descendants.remove(0);
+    descendants
+}
+
+fn is_valid_signal(signal: c_int) -> bool {
+    #[cfg(target_os = "linux")]
+    if (libc::SIGRTMIN()..=libc::SIGRTMAX()).contains(&signal) {
+        return true;
+    }
+    Signal::try_from(signal).is_ok()
+}
+
+#[cfg(target_os = "linux")]
+fn parse_realtime_signal(name: &str) -> Option<c_int> {
+    let (base, offset) = if let Some(offset) = name.strip_prefix("RTMIN") {
+        (libc::SIGRTMIN(), offset)
+    } else if let Some(offset) = name.strip_prefix("RTMAX") {
+        (libc::SIGRTMAX(), offset)
+    } else {
+        return None;
+    };
+    let signal = match offset {
+        "" => base,
+        _ => match (offset.strip_prefix('+'), offset.strip_prefix('-')) {
+            (Some(n), _) => base + n.parse::<c_int>().ok()?,
+            (_, Some(n)) => base - n.parse::<c_int>().ok()?,
+            _ => return None,
+        },
+    };
+    (libc::SIGRTMIN()..=libc::SIGRTMAX())
+        .contains(&signal)
+        .then_some(signal)
+}
@@ -0,0 +1,969 @@
+use crate::{
+    alarm::Alarm,
+    control::{self, Command, ControlSocket, Request},
+    health::{Monitor, Probed, UnhealthyAction},
+    logging::event,
+    restart::{NoRestart, Restarts},
+    shutdown::Escalation,
+    signal::{self, signal_name, ChildProcess},
+    tty, HealthCheck, MainExitPolicy, Pid1Settings,
+};
+use nix::{
+    errno::Errno,
+    sys::wait::{waitpid, WaitPidFlag, WaitStatus},
+};
+use signal_hook::{
+    consts::{SIGCHLD, SIGCONT, SIGINT, SIGKILL, SIGTERM},
+    iterator::Signals,
+};
+use std::{ffi::c_int, fmt, process::Child, time::Instant};
+
+/// How the main child process ended.
+#[derive(Debug)]
+pub enum ChildStatus {
+    /// The child exited with the given exit code.
+    Exited(i32),
+    /// The child was killed by the given signal. The flag tells
+    /// whether it dumped core.
+    Signaled(c_int, bool),
+    /// The child could not be spawned.
+    SpawnFailed(std::io::Error),
+}
+
+impl ChildStatus {
+    /// Conventional exit code for this status: the exit code of the
+    /// child, `128 + signal` if it was killed by a signal, and like
+    /// [`crate::Error::exit_code`] `127` if the executable could not be
+    /// found or `126` if it could not be run.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ChildStatus::Exited(exit_code) => *exit_code,
+            ChildStatus::Signaled(signal, _) => 128 + signal,
+            ChildStatus::SpawnFailed(e) => crate::spawn_failure_exit_code(e),
+        }
+    }
+}
+
+/// A process reaped by PID 1, see
+/// [`crate::Pid1Observer::process_reaped`].
+#[derive(Debug)]
+pub struct ReapedProcess {
+    /// PID of the process.
+    pub pid: i32,
+    /// How the process ended.
+    pub status: ChildStatus,
+    /// Whether the process was an orphan adopted by PID 1, rather than
+    /// the main child or one of the [`crate::Service`]s.
+    pub orphan: bool,
+}
+
+impl ReapedProcess {
+    /// The exit code of the process, if it exited normally.
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.status {
+            ChildStatus::Exited(exit_code) => Some(exit_code),
+            _ => None,
+        }
+    }
+
+    /// The signal which killed the process, if any.
+    pub fn signal(&self) -> Option<c_int> {
+        match self.status {
+            ChildStatus::Signaled(signal, _) => Some(signal),
+            _ => None,
+        }
+    }
+
+    /// Whether the process dumped core when it was killed.
+    pub fn core_dumped(&self) -> bool {
+        matches!(self.status, ChildStatus::Signaled(_, true))
+    }
+}
+
+/// Renders as `PID 42 was killed by SIGSEGV (core dumped)` or
+/// `PID 43 (orphan) exited with code 0`.
+impl fmt::Display for ReapedProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PID {}", self.pid)?;
+        if self.orphan {
+            f.write_str(" (orphan)")?;
+        }
+        match &self.status {
+            ChildStatus::Exited(exit_code) => write!(f, " exited with code {exit_code}"),
+            ChildStatus::Signaled(signal, core_dumped) => {
+                write!(f, " was killed by {}", signal_name(*signal))?;
+                if *core_dumped {
+                    f.write_str(" (core dumped)")?;
+                }
+                Ok(())
+            }
+            ChildStatus::SpawnFailed(e) => write!(f, " failed to spawn: {e}"),
+        }
+    }
+}
+
+/// The result of supervising a child process with
+/// [`Pid1Settings::supervise`].
+#[derive(Debug)]
+pub struct SupervisionOutcome {
+    /// How the main child process ended. When supervising several
+    /// [`crate::Service`]s, this is the status of the first critical
+    /// service to exit, or of the last service to exit if none of them
+    /// is critical.
+    pub status: ChildStatus,
+    /// Number of processes other than the main child which were
+    /// reaped, i.e. orphans adopted by PID 1.
+    pub orphans_reaped: usize,
+    /// Number of times the main child was restarted, see
+    /// [`crate::RestartPolicy`].
+    pub restarts: u32,
+    exit_code: i32,
+}
+
+impl SupervisionOutcome {
+    /// Exit code PID 1 should exit with: the
+    /// [`ChildStatus::exit_code`] of the status, translated with
+    /// [`Pid1Settings::map_exit_code`].
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+}
+
+/// Where the supervision is at after handling an event.
+pub(crate) enum Progress {
+    /// Still supervising. The loop needs to wake up at the deadline,
+    /// if any, even without signals arriving.
+    Running(Option<Instant>),
+    Finished(SupervisionOutcome),
+}
+
+/// Spawns the main child again, to restart it.
+pub(crate) type Respawn<'a> = Box<dyn FnMut() -> std::io::Result<Child> + 'a>;
+
+/// A supervised child process.
+struct Supervised<'a> {
+    /// `None` for the main child when supervising a single process.
+    name: Option<String>,
+    /// Whether the exit of this child stops all the others.
+    critical: bool,
+    /// The child, while it is running.
+    process: Option<ChildProcess>,
+    /// Without it, the child is never restarted.
+    respawn: Option<Respawn<'a>>,
+    restarts: Restarts,
+    /// The child exited with the given status and is restarted at the
+    /// deadline.
+    pending_restart: Option<(Instant, ChildStatus)>,
+    /// Reported instead of a successful exit when giving up on a
+    /// crash loop.
+    last_failure: Option<ChildStatus>,
+    health: Option<Monitor>,
+    /// The child is being stopped to be restarted right away, because
+    /// it is unhealthy or it was asked to on the control socket.
+    restarting: Option<Escalation>,
+    /// Exit code of the child, once it ended for good.
+    exited: Option<i32>,
+    /// The child was stopped by a job control signal and has not been
+    /// continued yet.
+    stopped: bool,
+}
+
+impl Supervised<'_> {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("Service {name}"),
+            None => "Main child".to_owned(),
+        }
+    }
+}
+
+/// State of the supervision of the child processes, independent of
+/// how signals are received.
+pub(crate) struct Supervision<'a> {
+    settings: &'a Pid1Settings,
+    children: Vec<Supervised<'a>>,
+    /// The children are being stopped, either because PID 1 was asked
+    /// to or because a critical child exited. They are not restarted
+    /// anymore.
+    stopping: bool,
+    /// When the running children started being stopped.
+    stopping_since: Option<Instant>,
+    shutdown: Option<Escalation>,
+    /// Status of the first critical child to exit.
+    critical_status: Option<ChildStatus>,
+    /// Status of the last child to exit.
+    last_status: Option<ChildStatus>,
+    /// Set once all the children have exited.
+    main_status: Option<ChildStatus>,
+    /// Deadline for the remaining processes to exit, when using
+    /// [`MainExitPolicy::TerminateDescendants`].
+    drain_deadline: Option<Instant>,
+    orphans_reaped: usize,
+    no_children: bool,
+    restart_count: u32,
+    control: Option<ControlSocket>,
+}
+
+impl<'a> Supervision<'a> {
+    /// A supervision without any children, add them with
+    /// [`Supervision::add_child`].
+    pub(crate) fn new(settings: &'a Pid1Settings) -> Self {
+        let control = settings.control_socket.as_ref().and_then(|path| {
+            ControlSocket::bind(path)
+                .map_err(|e| {
+                    let path = path.display();
+                    event!(settings, Error, "Failed to listen on {path}: {e}");
+                })
+                .ok()
+        });
+        Self {
+            settings,
+            children: Vec::new(),
+            stopping: false,
+            stopping_since: None,
+            shutdown: None,
+            critical_status: None,
+            last_status: None,
+            main_status: None,
+            drain_deadline: None,
+            orphans_reaped: 0,
+            no_children: false,
+            restart_count: 0,
+            control,
+        }
+    }
+
+    /// Supervise a child which was spawned with the given result. It
+    /// is restarted with `respawn` when it exits, according to the
+    /// [`crate::RestartPolicy`], and probed with the `health` check.
+    pub(crate) fn add_child(
+        &mut self,
+        name: Option<String>,
+        critical: bool,
+        spawned: std::io::Result<u32>,
+        respawn: Option<Respawn<'a>>,
+        health: Option<HealthCheck>,
+    ) {
+        self.children.push(Supervised {
+            name,
+            critical,
+            process: None,
+            respawn,
+            restarts: Restarts::new(self.settings.restart_policy.clone()),
+            pending_restart: None,
+            last_failure: None,
+            health: health.map(Monitor::new),
+            restarting: None,
+            exited: None,
+            stopped: false,
+        });
+        let index = self.children.len() - 1;
+        match spawned {
+            Ok(pid) => {
+                self.children[index].process = Some(ChildProcess::new(pid as i32));
+                self.no_children = false;
+            }
+            Err(e) => {
+                let label = self.children[index].label();
+                event!(self.settings, Error, "{label} failed to spawn: {e}");
+                self.record_exit(index, ChildStatus::SpawnFailed(e));
+            }
+        }
+    }
+
+    #[cfg(all(target_os = "linux", feature = "epoll"))]
+    pub(crate) fn settings(&self) -> &'a Pid1Settings {
+        self.settings
+    }
+
+    /// The pidfds of the running children and health check probes, see
+    /// [`ChildProcess`].
+    #[cfg(all(target_os = "linux", feature = "epoll"))]
+    pub(crate) fn pidfds(&self) -> impl Iterator<Item = std::os::fd::BorrowedFd<'_>> {
+        let probes = self
+            .children
+            .iter()
+            .filter_map(|child| child.health.as_ref());
+        self.running()
+            .filter_map(ChildProcess::pidfd)
+            .chain(probes.filter_map(Monitor::pidfd))
+    }
+
+    /// The running children.
+    fn running(&self) -> impl Iterator<Item = &ChildProcess> {
+        self.children
+            .iter()
+            .filter_map(|child| child.process.as_ref())
+    }
+
+    /// Handle a signal received by PID 1.
+    pub(crate) fn received(&mut self, signal: c_int) {
+        if signal == SIGCHLD {
+            self.reap();
+        } else {
+            self.handle_signal(signal);
+        }
+    }
+
+    /// Handle a signal received by PID 1, other than `SIGCHLD`.
+    pub(crate) fn handle_signal(&mut self, signal: c_int) {
+        let settings = self.settings;
+        let name = signal_name(signal);
+        event!(
+            settings,
+            Debug,
+            { signal = name.as_str() },
+            "Received {name}"
+        );
+        settings.observe(|observer| observer.signal_received(signal));
+        match settings.rewritten(signal) {
+            None => {
+                event!(
+                    settings,
+                    Info,
+                    { signal = name.as_str() },
+                    "Ignoring {name}"
+                );
+            }
+            Some(rewritten) if signal == SIGTERM || signal == SIGINT => {
+                // The shutdown steps are taken in `poll`, as their
+                // deadlines pass. We do not exit here since we want the
+                // SIGCHLD handler to be invoked appropriately.
+                self.stop(rewritten);
+            }
+            Some(rewritten) if settings.is_forwarded(signal) => {
+                let name = signal_name(rewritten);
+                for process in self.running() {
+                    let (target, result) = send_to_child(settings, process, rewritten);
+                    match result {
+                        Ok(()) => {
+                            event!(
+                                settings,
+                                Info,
+                                { pid = target, signal = name.as_str() },
+                                "Forwarded {name} to PID {target}"
+                            );
+                            settings
+                                .observe(|observer| observer.signal_forwarded(rewritten, target));
+                        }
+                        Err(e) => event!(
+                            settings,
+                            Warn,
+                            { pid = target, signal = name.as_str() },
+                            "Failed to forward {name} to PID {target}: {e}"
+                        ),
+                    }
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Stop all the children, starting the shutdown with `signal`.
+    fn stop(&mut self, signal: c_int) {
+        if self.stopping {
+            return;
+        }
+        self.stopping = true;
+        // Nothing to stop for the children waiting to be restarted
+        for index in 0..self.children.len() {
+            // The shutdown takes over from the children being
+            // restarted
+            self.children[index].restarting = None;
+            if let Some((_, status)) = self.children[index].pending_restart.take() {
+                self.record_exit(index, status);
+            }
+        }
+        if self.running().next().is_some() {
+            let policy = self.settings.effective_shutdown_policy();
+            self.shutdown = Some(Escalation::new(policy, signal));
+            self.stopping_since = Some(Instant::now());
+            self.settings
+                .observe(|observer| observer.shutdown_started(signal));
+        }
+    }
+
+    /// Reap all the child processes which have exited.
+    pub(crate) fn reap(&mut self) {
+        let settings = self.settings;
+        // Multiple child processes can exit in quick succession, but the
+        // operating system may only deliver a single SIGCHLD signal.
+        // This is known as signal coalescing. To handle this, we loop
+        // with a non-blocking `waitpid` call to reap all zombies.
+        // Using a blocking `wait` would hang if there are no more
+        // children to reap, preventing us from handling other signals.
+        // Reference: https://stackoverflow.com/a/8398491/1651941
+        loop {
+            let flags = WaitPidFlag::WNOHANG | WaitPidFlag::WUNTRACED | WaitPidFlag::WCONTINUED;
+            let wait_status = match waitpid(None, Some(flags)) {
+                Ok(status) => status,
+                Err(Errno::ECHILD) => {
+                    // No more children to wait for
+                    self.no_children();
+                    break;
+                }
+                Err(e) => {
+                    event!(settings, Error, "Error in waitpid: {e}");
+                    break;
+                }
+            };
+
+            let (pid, status) = match wait_status {
+                WaitStatus::Exited(pid, exit_code) => (pid, ChildStatus::Exited(exit_code)),
+                WaitStatus::Signaled(pid, signal, core_dumped) => {
+                    (pid, ChildStatus::Signaled(signal as c_int, core_dumped))
+                }
+                WaitStatus::StillAlive => {
+                    // No more children to reap now
+                    break;
+                }
+                WaitStatus::Stopped(pid, signal) => {
+                    self.job_control(pid.as_raw(), Some(signal as c_int));
+                    continue;
+                }
+                WaitStatus::Continued(pid) => {
+                    self.job_control(pid.as_raw(), None);
+                    continue;
+                }
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                WaitStatus::PtraceEvent(..) | WaitStatus::PtraceSyscall(..) => continue,
+            };
+
+            self.reaped(pid.as_raw(), status);
+        }
+    }
+
+    /// Record that `pid` was reaped with `status`.
+    pub(crate) fn reaped(&mut self, pid: i32, status: ChildStatus) {
+        let settings = self.settings;
+        // Whether others are left is only known once waitpid fails
+        self.no_children = false;
+        for child in &mut self.children {
+            if let Some(health) = &mut child.health {
+                if health.reaped(pid, &status) {
+                    return;
+                }
+            }
+        }
+        let index = self
+            .children
+            .iter()
+            .position(|child| child.process.as_ref().map(ChildProcess::pid) == Some(pid));
+        let process = ReapedProcess {
+            pid,
+            status,
+            orphan: index.is_none(),
+        };
+        event!(
+            settings,
+            Info,
+            {
+                pid = pid,
+                exit_code = process.status.exit_code(),
+                core_dumped = process.core_dumped(),
+                orphan = process.orphan,
+            },
+            "Reaped {process}"
+        );
+        settings.observe(|observer| observer.process_reaped(&process));
+        match index {
+            // One of the supervised children has exited. We'll act on
+            // its status in `poll`, but only after reaping any other
+            // children that may have exited in this same signal batch.
+            Some(index) => self.child_exited(index, process.status),
+            None => self.orphans_reaped += 1,
+        }
+    }
+
+    /// Record that `pid` was stopped by `signal`, or continued.
+    fn job_control(&mut self, pid: i32, stopped_by: Option<c_int>) {
+        let settings = self.settings;
+        let child = self
+            .children
+            .iter_mut()
+            .find(|child| child.process.as_ref().map(ChildProcess::pid) == Some(pid));
+        let label = match child {
+            Some(child) => {
+                child.stopped = stopped_by.is_some();
+                format!("{} (PID {pid})", child.label())
+            }
+            None => format!("PID {pid}"),
+        };
+        match stopped_by {
+            Some(signal) => {
+                let name = signal_name(signal);
+                event!(
+                    settings,
+                    Info,
+                    { pid = pid, signal = name.as_str() },
+                    "{label} was stopped by {name}"
+                );
+            }
+            None => event!(settings, Info, { pid = pid }, "{label} continued"),
+        }
+    }
+
+    /// Record that there are no child processes left.
+    pub(crate) fn no_children(&mut self) {
+        self.no_children = true;
+    }
+
+    fn child_exited(&mut self, index: usize, mut status: ChildStatus) {
+        let settings = self.settings;
+        settings.observe(|observer| observer.main_exited(&status));
+        let child = &mut self.children[index];
+        child.process = None;
+        child.stopped = false;
+        if child.restarting.take().is_some() && !self.stopping {
+            let label = child.label();
+            event!(settings, Info, "{label} exited, restarting it");
+            child.pending_restart = Some((Instant::now(), status));
+            return;
+        }
+        if !self.stopping && child.respawn.is_some() {
+            let failed = status.exit_code() != 0;
+            let label = child.label();
+            match child.restarts.next_delay(failed) {
+                Ok(delay) => {
+                    event!(settings, Info, "{label} exited, restarting in {delay:?}");
+                    child.pending_restart = Some((Instant::now() + delay, status));
+                    return;
+                }
+                Err(NoRestart::CrashLoop { count, window }) => {
+                    event!(
+                        settings,
+                        Error,
+                        "{label} restarted {count} times within {window:?}, giving up"
+                    );
+                    if !failed {
+                        status = child.last_failure.take().unwrap_or(status);
+                    }
+                }
+                Err(NoRestart::Policy) => {}
+            }
+        }
+        self.record_exit(index, status);
+    }
+
+    /// Record that the child at `index` ended for good with `status`.
+    fn record_exit(&mut self, index: usize, status: ChildStatus) {
+        self.children[index].exited = Some(status.exit_code());
+        let child = &self.children[index];
+        if self.children.len() > 1 {
+            let label = child.label();
+            let exit_code = status.exit_code();
+            event!(self.settings, Info, "{label} exited with code {exit_code}");
+        }
+        if child.critical && self.critical_status.is_none() {
+            self.critical_status = Some(status);
+            let signal = self.settings.rewritten(SIGTERM).unwrap_or(SIGTERM);
+            self.stop(signal);
+        } else {
+            self.last_status = Some(status);
+        }
+    }
+
+    fn all_exited(&self) -> bool {
+        self.children
+            .iter()
+            .all(|child| child.process.is_none() && child.pending_restart.is_none())
+    }
+
+    /// Stop supervising once all the children have exited.
+    fn finish(&mut self) {
+        // The remaining shutdown steps are skipped
+        self.shutdown = None;
+        if let Some(since) = self.stopping_since {
+            let elapsed = since.elapsed();
+            self.settings
+                .observe(|observer| observer.shutdown_finished(elapsed));
+        }
+        if self.settings.tty {
+            if let Err(e) = tty::restore_terminal() {
+                event!(self.settings, Warn, "Failed to take back the terminal: {e}");
+            }
+        }
+        let status = self
+            .critical_status
+            .take()
+            .or_else(|| self.last_status.take())
+            .unwrap_or(ChildStatus::Exited(0));
+        self.main_status = Some(status);
+        if let MainExitPolicy::TerminateDescendants(wait) = self.settings.on_main_exit {
+            event!(
+                self.settings,
+                Info,
+                "Main child exited, sending SIGTERM to remaining processes"
+            );
+            // Stopped processes would not act on SIGTERM otherwise
+            signal::signal_descendants(SIGCONT);
+            signal::signal_descendants(SIGTERM);
+            self.drain_deadline = Some(Instant::now() + wait);
+        }
+    }
+
+    /// Take the actions which are due and check whether the
+    /// supervision is over.
+    pub(crate) fn poll(&mut self) -> Progress {
+        while let Some(request) = self.control.as_ref().and_then(ControlSocket::next_request) {
+            self.handle_request(request);
+        }
+        let mut next_deadline = None;
+        for index in 0..self.children.len() {
+            if !self.stopping {
+                next_deadline = earliest(next_deadline, self.advance_restart(index));
+                next_deadline = earliest(next_deadline, self.check_health(index));
+            }
+            if let Some((deadline, _)) = self.children[index].pending_restart {
+                if Instant::now() < deadline {
+                    next_deadline = earliest(next_deadline, Some(deadline));
+                } else {
+                    self.restart(index);
+                }
+            }
+        }
+
+        if self.main_status.is_none() && self.all_exited() {
+            self.finish();
+        }
+        if self.main_status.is_some() {
+            if let (Some(deadline), false) = (self.drain_deadline, self.no_children) {
+                if Instant::now() < deadline {
+                    return Progress::Running(Some(deadline));
+                }
+                event!(
+                    self.settings,
+                    Warn,
+                    "Sending SIGKILL to remaining processes"
+                );
+                signal::signal_descendants(SIGKILL);
+            }
+            let status = self.main_status.take().expect("main child exited");
+            let exit_code = status.exit_code();
+            let mapped = self.settings.mapped_exit_code(exit_code);
+            if mapped != exit_code {
+                event!(
+                    self.settings,
+                    Info,
+                    { exit_code = mapped },
+                    "Mapped exit code {exit_code} to {mapped}"
+                );
+            }
+            return Progress::Finished(SupervisionOutcome {
+                status,
+                orphans_reaped: self.orphans_reaped,
+                restarts: self.restart_count,
+                exit_code: mapped,
+            });
+        }
+
+        let children = &self.children;
+        let Some(shutdown) = &mut self.shutdown else {
+            return Progress::Running(next_deadline);
+        };
+        let settings = self.settings;
+        let deadline = shutdown.advance(|signal, step| {
+            let name = signal_name(signal);
+            let step = step + 1;
+            for child in children {
+                let Some(process) = &child.process else {
+                    continue;
+                };
+                let (target, result) = stop_child(settings, process, child.stopped, signal);
+                match result {
+                    Ok(()) => event!(
+                        settings,
+                        Info,
+                        { pid = target, signal = name.as_str(), step = step },
+                        "Shutdown step {step}: sent {name} to PID {target}"
+                    ),
+                    Err(e) => event!(
+                        settings,
+                        Warn,
+                        { pid = target, signal = name.as_str(), step = step },
+                        "Shutdown step {step}: failed to send {name} to PID {target}: {e}"
+                    ),
+                }
+                settings.observe(|observer| observer.shutdown_step(step, signal, target));
+            }
+        });
+        Progress::Running(earliest(next_deadline, deadline))
+    }
+
+    fn restart(&mut self, index: usize) {
+        let child = &mut self.children[index];
+        let Some((_, status)) = child.pending_restart.take() else {
+            return;
+        };
+        let label = child.label();
+        let Some(respawn) = &mut child.respawn else {
+            return self.record_exit(index, status);
+        };
+        match respawn() {
+            Ok(spawned) => {
+                let pid = spawned.id() as i32;
+                child.process = Some(ChildProcess::new(pid));
+                self.no_children = false;
+                if let Some(health) = &mut child.health {
+                    health.reset();
+                }
+                if status.exit_code() != 0 {
+                    child.last_failure = Some(status);
+                }
+                self.restart_count += 1;
+                self.settings
+                    .observe(|observer| observer.process_restarted(pid));
+                event!(
+                    self.settings,
+                    Info,
+                    { pid = pid },
+                    "{label} restarted as PID {pid}"
+                );
+            }
+            Err(e) => {
+                event!(self.settings, Error, "{label} failed to restart: {e}");
+                self.record_exit(index, ChildStatus::SpawnFailed(e));
+            }
+        }
+    }
+}
+
+impl Supervision<'_> {
+    /// Run a command received on the control socket and reply with its
+    /// output.
+    fn handle_request(&mut self, request: Request) {
+        let settings = self.settings;
+        let command = request.command;
+        event!(settings, Info, "Received `{command}` on the control socket");
+        let response = match command {
+            Command::Status => Ok(self.status()),
+            Command::Signal(signal) if settings.handled_signals().contains(&signal) => {
+                self.received(signal);
+                Ok(String::new())
+            }
+            Command::Signal(signal) => {
+                Err(format!("signal {} is not forwarded", signal_name(signal)))
+            }
+            Command::Restart => self.restart_all(),
+            Command::Stop => {
+                self.stop(settings.rewritten(SIGTERM).unwrap_or(SIGTERM));
+                Ok(String::new())
+            }
+            Command::Ps => Ok(control::ps()),
+        };
+        request.reply(response);
+    }
+
+    /// Output of the `status` command.
+    fn status(&self) -> String {
+        let state = if self.stopping { "stopping" } else { "running" };
+        let mut output = format!(
+            "state: {state}\nrestarts: {}\norphans-reaped: {}\n",
+            self.restart_count, self.orphans_reaped
+        );
+        for child in &self.children {
+            let name = child.name.as_deref().unwrap_or("main");
+            let state = match (&child.process, child.exited) {
+                (Some(process), _) if child.stopped => format!("stopped (PID {})", process.pid()),
+                (Some(process), _) => format!("running (PID {})", process.pid()),
+                (None, Some(exit_code)) => format!("exited with code {exit_code}"),
+                (None, None) => "restarting".to_owned(),
+            };
+            output.push_str(&format!("{name}: {state}\n"));
+        }
+        output
+    }
+
+    /// Stop the running children which can be respawned, to restart
+    /// them right away.
+    fn restart_all(&mut self) -> Result<String, String> {
+        if self.stopping {
+            return Err("the children are being stopped".to_owned());
+        }
+        let settings = self.settings;
+        let signal = settings.rewritten(SIGTERM).unwrap_or(SIGTERM);
+        let mut restarting = 0;
+        for child in &mut self.children {
+            if child.process.is_some() && child.respawn.is_some() && child.restarting.is_none() {
+                let label = child.label();
+                event!(settings, Info, "{label} is being restarted");
+                let policy = settings.effective_shutdown_policy();
+                child.restarting = Some(Escalation::new(policy, signal));
+                restarting += 1;
+            }
+        }
+        match restarting {
+            0 => Err("no child can be restarted".to_owned()),
+            _ => Ok(String::new()),
+        }
+    }
+
+    /// Take the steps which are due to stop the child at `index`, when
+    /// it is being restarted. Returns when the next step is due.
+    fn advance_restart(&mut self, index: usize) -> Option<Instant> {
+        let settings = self.settings;
+        let child = &mut self.children[index];
+        let process = child.process.as_ref()?;
+        child.restarting.as_mut()?.advance(|signal, _| {
+            let name = signal_name(signal);
+            let (target, result) = stop_child(settings, process, child.stopped, signal);
+            match result {
+                Ok(()) => event!(
+                    settings,
+                    Info,
+                    { pid = target, signal = name.as_str() },
+                    "Sent {name} to PID {target} to restart it"
+                ),
+                Err(e) => event!(
+                    settings,
+                    Warn,
+                    { pid = target, signal = name.as_str() },
+                    "Failed to send {name} to PID {target} to restart it: {e}"
+                ),
+            }
+        })
+    }
+
+    /// Run the health check of the child at `index` and act on its
+    /// result. Returns when it needs to be polled again.
+    fn check_health(&mut self, index: usize) -> Option<Instant> {
+        let settings = self.settings;
+        let child = &mut self.children[index];
+        let label = child.label();
+        let process = child.process.as_ref()?;
+        let pid = process.pid();
+        if child.restarting.is_some() {
+            return None;
+        }
+        let health = child.health.as_mut()?;
+        let (probed, next_poll) = health.poll();
+        match probed {
+            None => {}
+            Some(Probed { result: Ok(()), .. }) => {
+                event!(settings, Debug, "{label} passed its health check")
+            }
+            Some(Probed {
+                result: Err(reason),
+                failures,
+                threshold,
+            }) => {
+                event!(
+                    settings,
+                    Warn,
+                    { pid = pid, failures = failures },
+                    "{label} failed its health check ({failures}/{threshold}): {reason}"
+                );
+                if failures == threshold {
+                    let action = health.action();
+                    self.unhealthy(index, action);
+                    // Stopping the child is due right away
+                    return Some(Instant::now());
+                }
+            }
+        }
+        Some(next_poll)
+    }
+
+    /// Take `action` on the child at `index`, which is unhealthy.
+    fn unhealthy(&mut self, index: usize, action: UnhealthyAction) {
+        let settings = self.settings;
+        let child = &mut self.children[index];
+        let label = child.label();
+        let signal = settings.rewritten(SIGTERM).unwrap_or(SIGTERM);
+        match action {
+            UnhealthyAction::Log => event!(settings, Error, "{label} is unhealthy"),
+            UnhealthyAction::Restart if child.respawn.is_some() => {
+                event!(settings, Error, "{label} is unhealthy, restarting it");
+                let policy = settings.effective_shutdown_policy();
+                child.restarting = Some(Escalation::new(policy, signal));
+            }
+            UnhealthyAction::Restart | UnhealthyAction::Shutdown => {
+                event!(settings, Error, "{label} is unhealthy, shutting down");
+                self.stop(signal);
+            }
+        }
+    }
+}
+
+/// Send `signal` to a child, or to its process group with
+/// [`Pid1Settings::process_group`]. Returns the PID the signal was sent
+/// to, see [`Pid1Settings::signal_target`].
+fn send_to_child(
+    settings: &Pid1Settings,
+    process: &ChildProcess,
+    signal: c_int,
+) -> (i32, Result<(), Errno>) {
+    let target = settings.signal_target(process.pid());
+    if target == process.pid() {
+        (target, process.send_signal(signal))
+    } else {
+        (target, signal::send_signal(target, signal))
+    }
+}
+
+/// Send `signal` to a child as part of stopping it. A child which was
+/// `stopped` by a job control signal only acts on it once continued,
+/// so it is sent `SIGCONT` first.
+fn stop_child(
+    settings: &Pid1Settings,
+    process: &ChildProcess,
+    stopped: bool,
+    signal: c_int,
+) -> (i32, Result<(), Errno>) {
+    if stopped && signal != SIGKILL && signal != SIGCONT {
+        let (target, result) = send_to_child(settings, process, SIGCONT);
+        match result {
+            Ok(()) => event!(
+                settings,
+                Info,
+                { pid = target },
+                "Sent SIGCONT to stopped PID {target}"
+            ),
+            Err(e) => event!(
+                settings,
+                Warn,
+                { pid = target },
+                "Failed to send SIGCONT to stopped PID {target}: {e}"
+            ),
+        }
+    }
+    send_to_child(settings, process, signal)
+}
+
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Supervise the children until they exit. Signals are received
+/// through signal-hook, or with the `epoll` feature through the
+/// [`crate::epoll::EventLoop`] unless it cannot be set up.
+pub(crate) fn supervise(
+    mut supervision: Supervision<'_>,
+    mut signals: Signals,
+) -> SupervisionOutcome {
+    #[cfg(all(target_os = "linux", feature = "epoll"))]
+    match crate::epoll::EventLoop::new(&supervision.settings.handled_signals()) {
+        Ok(event_loop) => return event_loop.run(supervision, signals),
+        Err(e) => event!(
+            supervision.settings,
+            Warn,
+            "Failed to set up the epoll event loop, using signal-hook: {e}"
+        ),
+    }
+    let mut alarm = Alarm::new();
+    let mut signals = signals.forever();
+    loop {
+        match supervision.poll() {
+            Progress::Running(Some(deadline)) => alarm.set(deadline),
+            Progress::Running(None) => {}
+            Progress::Finished(outcome) => return outcome,
+        }
+        let Some(signal) = signals.next() else {
+            continue;
+        };
+        supervision.received(signal);
+    }
+}
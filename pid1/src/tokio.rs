@@ -0,0 +1,197 @@
+//! Async supervision for applications running a tokio runtime,
+//! available with the `tokio` feature.
+//!
+//! ```no_run
+//! use pid1::{tokio::Supervisor, Pid1Settings};
+//! use tokio::process::Command;
+//!
+//! # async fn run() -> Result<(), pid1::Error> {
+//! let settings = Pid1Settings::new();
+//! let supervisor = Supervisor::spawn(&settings, &mut Command::new("my-server"))?;
+//! let outcome = supervisor.wait().await?;
+//! std::process::exit(outcome.exit_code());
+//! # }
+//! ```
+
+use crate::{
+    logging::event,
+    supervise::{ChildStatus, Progress, Supervision, SupervisionOutcome},
+    Error, Pid1Settings,
+};
+use ::tokio::{
+    process::{Child, Command},
+    signal::unix::{signal, Signal, SignalKind},
+    time::sleep_until,
+};
+use nix::{
+    errno::Errno,
+    sys::wait::{waitpid, WaitPidFlag, WaitStatus},
+    unistd::Pid,
+};
+use signal_hook::consts::SIGCHLD;
+use std::{
+    ffi::c_int,
+    future::{pending, poll_fn},
+    os::unix::process::ExitStatusExt,
+    task::Poll,
+    time::Instant,
+};
+
+/// Supervises a child process from within a tokio runtime: signals
+/// are forwarded to it, orphans are reaped and the shutdown policy is
+/// applied, as with [`Pid1Settings::supervise`]. The
+/// [`Pid1Settings::restart_policy`] is not applied.
+///
+/// Like the blocking API, this reaps every child of the process, so
+/// other child processes spawned by the application cannot be waited
+/// on.
+#[derive(Debug)]
+pub struct Supervisor {
+    settings: Pid1Settings,
+    signals: Vec<(c_int, Signal)>,
+    child: Child,
+    pid: i32,
+}
+
+impl Supervisor {
+    /// Install the signal handlers and spawn `command`. This has to be
+    /// called from within a tokio runtime with IO and time enabled.
+    pub fn spawn(settings: &Pid1Settings, command: &mut Command) -> Result<Self, Error> {
+        // The handlers are installed before spawning, so that no
+        // signal is missed.
+        let signals = settings
+            .handled_signals()
+            .into_iter()
+            .map(|number| Ok((number, signal(SignalKind::from_raw(number))?)))
+            .collect::<std::io::Result<_>>()
+            .map_err(Error::SignalRegistration)?;
+        if settings.process_group {
+            command.process_group(0);
+        }
+        if settings.tty {
+            crate::tty::foreground(command.as_std_mut());
+        }
+        let child = command.spawn().map_err(Error::SpawnChild)?;
+        let pid = child.id().expect("child has not been polled to completion") as i32;
+        Ok(Self {
+            settings: settings.clone(),
+            signals,
+            child,
+            pid,
+        })
+    }
+
+    /// PID of the main child process.
+    pub fn id(&self) -> u32 {
+        self.pid as u32
+    }
+
+    /// Supervise the child process until it exits and return how it
+    /// ended. This fails if tokio cannot wait for the child process.
+    pub async fn wait(mut self) -> Result<SupervisionOutcome, Error> {
+        let mut supervision = Supervision::new(&self.settings);
+        let health_check = self.settings.health_check.clone();
+        supervision.add_child(None, true, Ok(self.pid as u32), None, health_check);
+        loop {
+            // Polling first starts the health checks and other deadlines
+            let deadline = match supervision.poll() {
+                Progress::Running(next) => next,
+                Progress::Finished(outcome) => return Ok(outcome),
+            };
+            let signal = ::tokio::select! {
+                signal = next_signal(&mut self.signals) => Some(signal),
+                () = sleep_or_pending(deadline) => None,
+            };
+            match signal {
+                Some(SIGCHLD) => {
+                    reap(&self.settings, &mut supervision, &mut self.child, self.pid).await?
+                }
+                Some(signal) => supervision.handle_signal(signal),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Wait for any of the registered signals.
+async fn next_signal(signals: &mut [(c_int, Signal)]) -> c_int {
+    poll_fn(|cx| {
+        for (number, signal) in signals.iter_mut() {
+            if signal.poll_recv(cx).is_ready() {
+                return Poll::Ready(*number);
+            }
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+async fn sleep_or_pending(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => sleep_until(deadline.into()).await,
+        None => pending().await,
+    }
+}
+
+/// Reap all the child processes which have exited. The main child is
+/// reaped by tokio, so each zombie is looked at with `WNOWAIT` first.
+async fn reap(
+    settings: &Pid1Settings,
+    supervision: &mut Supervision<'_>,
+    child: &mut Child,
+    pid: i32,
+) -> Result<(), Error> {
+    loop {
+        let zombie = match peek_exited() {
+            Ok(Some(zombie)) => zombie,
+            Ok(None) => break,
+            Err(Errno::ECHILD) => {
+                supervision.no_children();
+                break;
+            }
+            Err(e) => {
+                event!(settings, Error, "Error in waitid: {e}");
+                break;
+            }
+        };
+        if zombie == pid {
+            let status = child.wait().await.map_err(Error::Wait)?;
+            let status = match (status.code(), status.signal()) {
+                (Some(code), _) => ChildStatus::Exited(code),
+                (None, Some(signal)) => ChildStatus::Signaled(signal, status.core_dumped()),
+                (None, None) => ChildStatus::Exited(1),
+            };
+            supervision.reaped(pid, status);
+            continue;
+        }
+        match waitpid(Pid::from_raw(zombie), Some(WaitPidFlag::WNOHANG)) {
+            Ok(WaitStatus::Exited(_, code)) => {
+                supervision.reaped(zombie, ChildStatus::Exited(code));
+            }
+            Ok(WaitStatus::Signaled(_, signal, core_dumped)) => {
+                supervision.reaped(zombie, ChildStatus::Signaled(signal as c_int, core_dumped));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                event!(settings, Error, "Error in waitpid: {e}");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// PID of a child process which has exited, without reaping it.
+fn peek_exited() -> Result<Option<i32>, Errno> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    Errno::result(unsafe {
+        libc::waitid(
+            libc::P_ALL,
+            0,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        )
+    })?;
+    let pid = unsafe { info.si_pid() };
+    Ok((pid != 0).then_some(pid))
+}
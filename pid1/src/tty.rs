@@ -0,0 +1,63 @@
+use std::{io, mem::MaybeUninit, os::unix::process::CommandExt, process::Command};
+
+/// Spawn `command` in its own process group and make it the foreground
+/// process group of the terminal, see [`crate::Pid1Settings::tty`].
+pub(crate) fn foreground(command: &mut Command) {
+    command.process_group(0);
+    // Only async-signal-safe functions are called in the child
+    unsafe { command.pre_exec(take_terminal) };
+}
+
+/// Make the process group of the calling process the foreground
+/// process group of the terminal on stdin, if any. This runs in the
+/// child between `fork` and `exec`, so it only makes async-signal-safe
+/// calls, and failures are ignored: the child then simply runs in the
+/// background.
+fn take_terminal() -> io::Result<()> {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+        let _ = with_sigttou_blocked(|| unsafe {
+            libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp())
+        });
+    }
+    Ok(())
+}
+
+/// Make the process group of PID 1 the foreground process group of
+/// the terminal on stdin again, after [`take_terminal`] handed it to a
+/// child. Does nothing when stdin is not a terminal, PID 1 already is
+/// in the foreground or its process group was created outside of the
+/// PID namespace, in which case it has no ID to refer to it.
+pub(crate) fn restore_terminal() -> io::Result<()> {
+    let pgrp = unsafe { libc::getpgrp() };
+    unsafe {
+        if pgrp == 0
+            || libc::isatty(libc::STDIN_FILENO) != 1
+            || libc::tcgetpgrp(libc::STDIN_FILENO) == pgrp
+        {
+            return Ok(());
+        }
+    }
+    with_sigttou_blocked(|| unsafe { libc::tcsetpgrp(libc::STDIN_FILENO, pgrp) })
+}
+
+/// Run `f` with `SIGTTOU` blocked in the calling thread. Changing the
+/// foreground process group from a background process group raises
+/// `SIGTTOU` otherwise, which would stop the process or, with a
+/// handler installed, make the call restart forever.
+fn with_sigttou_blocked(f: impl FnOnce() -> libc::c_int) -> io::Result<()> {
+    unsafe {
+        let mut mask = MaybeUninit::<libc::sigset_t>::uninit();
+        libc::sigemptyset(mask.as_mut_ptr());
+        libc::sigaddset(mask.as_mut_ptr(), libc::SIGTTOU);
+        let mut previous = MaybeUninit::<libc::sigset_t>::uninit();
+        libc::pthread_sigmask(libc::SIG_BLOCK, mask.as_ptr(), previous.as_mut_ptr());
+        let result = f();
+        let error = io::Error::last_os_error();
+        libc::pthread_sigmask(libc::SIG_SETMASK, previous.as_ptr(), std::ptr::null_mut());
+        if result == -1 {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+}
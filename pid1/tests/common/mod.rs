@@ -0,0 +1,13 @@
+//! Helpers shared by the integration tests.
+//!
+//! Supervision reaps any child of the test process, so the tests of a
+//! file spawning children run them sequentially, from a single test.
+
+use std::process::Command;
+
+/// A command running `script` with `sh -c`.
+pub fn sh(script: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.args(["-c", script]);
+    command
+}
@@ -0,0 +1,98 @@
+#![cfg(target_family = "unix")]
+
+use pid1::{bind_unix_socket, ChildStatus, Pid1Settings, Restart, RestartPolicy};
+use signal_hook::consts::{SIGHUP, SIGTERM};
+use std::{
+    io::{Read, Write},
+    os::unix::{fs::PermissionsExt, net::UnixStream},
+    path::Path,
+    process::Command,
+    time::Duration,
+};
+
+fn send(path: &Path, command: &str) -> String {
+    // The socket is bound once the supervision started
+    let mut stream = loop {
+        match UnixStream::connect(path) {
+            Ok(stream) => break stream,
+            Err(_) => std::thread::sleep(Duration::from_millis(10)),
+        }
+    };
+    writeln!(stream, "{command}").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn bind_replaces_stale_socket() {
+    let path = std::env::temp_dir().join(format!("pid1-bind-{}.sock", std::process::id()));
+    drop(bind_unix_socket(&path).unwrap());
+    assert!(path.exists());
+    drop(bind_unix_socket(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    std::fs::write(&path, "keep me").unwrap();
+    let err = bind_unix_socket(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn control_socket_commands() {
+    let path = std::env::temp_dir().join(format!("pid1-control-{}.sock", std::process::id()));
+    let delay = Duration::from_millis(10);
+    let mut settings = Pid1Settings::new();
+    settings
+        .control_socket(&path)
+        .forward_signals([SIGHUP])
+        .restart_policy(RestartPolicy::new(Restart::Always).backoff(delay, delay));
+
+    let client = std::thread::spawn({
+        let path = path.clone();
+        move || {
+            let status = send(&path, "status");
+            assert!(status.starts_with("ok\nstate: running\nrestarts: 0\n"));
+            assert!(status.contains("\nmain: running (PID "));
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            assert!(send(&path, "ps").contains(" sleep 10\n"));
+            assert_eq!(
+                send(&path, "signal USR1"),
+                "error: signal SIGUSR1 is not forwarded\n"
+            );
+            assert_eq!(
+                send(&path, "signal KILL"),
+                "error: signal SIGKILL is not forwarded\n"
+            );
+            // `sleep` is killed by SIGHUP and restarted
+            assert_eq!(send(&path, "signal HUP"), "ok\n");
+            while !send(&path, "status").contains("restarts: 1\n") {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            assert_eq!(send(&path, "restart"), "ok\n");
+            while !send(&path, "status").contains("restarts: 2\n") {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            assert_eq!(send(&path, "signal NOPE"), "error: unknown signal `NOPE`\n");
+            assert_eq!(send(&path, "stop"), "ok\n");
+        }
+    });
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, Command::new("sleep").arg("10"));
+    client.join().unwrap();
+    assert!(matches!(outcome.status, ChildStatus::Signaled(SIGTERM, _)));
+    assert_eq!(outcome.restarts, 2);
+    assert!(!path.exists());
+
+    // Only a socket is replaced
+    std::fs::write(&path, "keep me").unwrap();
+    let mut settings = Pid1Settings::new();
+    settings.control_socket(&path);
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut Command::new("true"));
+    assert!(matches!(outcome.status, ChildStatus::Exited(0)));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,24 @@
+use pid1::Error;
+use std::io::{Error as IoError, ErrorKind};
+
+#[test]
+fn error_exit_codes() {
+    let not_found = || IoError::from(ErrorKind::NotFound);
+    let denied = || IoError::from(ErrorKind::PermissionDenied);
+    assert_eq!(Error::SpawnChild(not_found()).exit_code(), 127);
+    assert_eq!(Error::SpawnChild(denied()).exit_code(), 126);
+    assert_eq!(Error::CurrentExe(not_found()).exit_code(), 127);
+    assert_eq!(Error::SignalRegistration(denied()).exit_code(), 1);
+    assert_eq!(Error::Subreaper(denied()).exit_code(), 1);
+    assert_eq!(Error::Wait(denied()).exit_code(), 1);
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn spawn_failed_exit_codes() {
+    use pid1::ChildStatus;
+    let status = ChildStatus::SpawnFailed(IoError::from(ErrorKind::NotFound));
+    assert_eq!(status.exit_code(), 127);
+    let status = ChildStatus::SpawnFailed(IoError::from(ErrorKind::PermissionDenied));
+    assert_eq!(status.exit_code(), 126);
+}
@@ -0,0 +1,126 @@
+#![cfg(target_family = "unix")]
+
+mod common;
+
+use common::sh;
+use pid1::{ChildStatus, HealthCheck, HealthProbe, Pid1Settings, UnhealthyAction};
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener},
+    time::{Duration, Instant},
+};
+
+#[test]
+fn parse_health_probe() {
+    let local = |port| SocketAddr::from(([127, 0, 0, 1], port));
+    assert_eq!(
+        "exec:pg_isready -q".parse(),
+        Ok(HealthProbe::Exec {
+            program: "pg_isready".to_owned(),
+            args: vec!["-q".to_owned()],
+        })
+    );
+    assert_eq!("tcp:5432".parse(), Ok(HealthProbe::Tcp(local(5432))));
+    assert_eq!(
+        "tcp:127.0.0.1:5432".parse(),
+        Ok(HealthProbe::Tcp(local(5432)))
+    );
+    assert_eq!(
+        "http:8080".parse(),
+        Ok(HealthProbe::Http {
+            addr: local(8080),
+            path: "/".to_owned(),
+        })
+    );
+    assert_eq!(
+        "http://127.0.0.1:8080/healthz".parse(),
+        Ok(HealthProbe::Http {
+            addr: local(8080),
+            path: "/healthz".to_owned(),
+        })
+    );
+    assert_eq!(
+        r#"exec:sh -c "curl -f localhost""#.parse(),
+        Ok(HealthProbe::Exec {
+            program: "sh".to_owned(),
+            args: vec!["-c".to_owned(), "curl -f localhost".to_owned()],
+        })
+    );
+    assert!("exec:".parse::<HealthProbe>().is_err());
+    assert!("exec:sh -c 'exit".parse::<HealthProbe>().is_err());
+    assert_eq!(
+        "tcp:nowhere"
+            .parse::<HealthProbe>()
+            .unwrap_err()
+            .to_string(),
+        "invalid `tcp:nowhere`: invalid address"
+    );
+    assert!("ping:8080".parse::<HealthProbe>().is_err());
+    assert_eq!("Restart".parse(), Ok(UnhealthyAction::Restart));
+    assert!("panic".parse::<UnhealthyAction>().is_err());
+}
+
+fn check(probe: HealthProbe) -> HealthCheck {
+    HealthCheck::new(probe)
+        .interval(Duration::from_millis(50))
+        .timeout(Duration::from_secs(1))
+}
+
+#[test]
+fn health_check() {
+    // A healthy child is left alone
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let probe = HealthProbe::Tcp(listener.local_addr().unwrap());
+    let mut settings = Pid1Settings::new();
+    settings.health_check(check(probe).action(UnhealthyAction::Shutdown));
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh("sleep 0.5"));
+    assert!(matches!(outcome.status, ChildStatus::Exited(0)));
+    drop(listener);
+
+    // An unhealthy child brings PID 1 down
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            let _ = stream.write_all(b"HTTP/1.0 503 Service Unavailable\r\n\r\n");
+        }
+    });
+    let probe = HealthProbe::Http {
+        addr,
+        path: "/healthz".to_owned(),
+    };
+    settings.health_check(
+        check(probe)
+            .failure_threshold(2)
+            .action(UnhealthyAction::Shutdown),
+    );
+    let start = Instant::now();
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh("exec sleep 10"));
+    assert!(matches!(outcome.status, ChildStatus::Signaled(15, _)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // An unhealthy child is restarted, whatever the restart policy
+    let marker = std::env::temp_dir().join(format!("pid1-health-{}", std::process::id()));
+    let script = format!(
+        "if [ -e {0} ]; then exit 4; fi; touch {0}; exec sleep 10",
+        marker.display()
+    );
+    let probe = HealthProbe::Exec {
+        program: "false".to_owned(),
+        args: Vec::new(),
+    };
+    settings.health_check(
+        check(probe)
+            .failure_threshold(1)
+            .action(UnhealthyAction::Restart),
+    );
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh(&script));
+    let _ = std::fs::remove_file(&marker);
+    assert!(matches!(outcome.status, ChildStatus::Exited(4)));
+    assert_eq!(outcome.restarts, 1);
+}
@@ -0,0 +1,38 @@
+#![cfg(target_family = "unix")]
+
+use pid1::{Metrics, Pid1Settings, Restart, RestartPolicy};
+use signal_hook::consts::SIGHUP;
+use std::{process::Command, sync::Arc, time::Duration};
+
+#[test]
+fn metrics_count_supervision_events() {
+    let metrics = Arc::new(Metrics::new());
+    let delay = Duration::from_millis(10);
+    let mut settings = Pid1Settings::new();
+    settings.observer(metrics.clone()).restart_policy(
+        RestartPolicy::new(Restart::OnFailure)
+            .backoff(delay, delay)
+            .max_restarts(1, Duration::from_secs(60)),
+    );
+
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(
+        signals,
+        Command::new("sh").args([
+            "-c",
+            "trap 'exit 3' HUP; kill -HUP $PPID; while true; do sleep 0.05; done",
+        ]),
+    );
+    assert_eq!(outcome.exit_code(), 3);
+    assert_eq!(metrics.restarts(), 1);
+    assert_eq!(metrics.processes_reaped(), 2);
+    assert_eq!(metrics.orphans_reaped(), 0);
+    assert_eq!(metrics.signals_received(SIGHUP), 2);
+    assert_eq!(metrics.shutdown_duration(), None);
+
+    let text = metrics.to_string();
+    assert!(text.contains("# TYPE pid1_restarts_total counter\npid1_restarts_total 1\n"));
+    assert!(text.contains("pid1_signals_received_total{signal=\"SIGHUP\"} 2\n"));
+    assert!(text.contains("pid1_signals_forwarded_total 2\n"));
+    assert!(text.contains("pid1_shutdown_duration_seconds 0\n"));
+}
@@ -0,0 +1,66 @@
+#![cfg(target_family = "unix")]
+
+use pid1::{ChildStatus, Pid1Observer, Pid1Settings, ReapedProcess};
+use signal_hook::consts::SIGHUP;
+use std::{
+    ffi::c_int,
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<String>>);
+
+impl Recorder {
+    fn record(&self, event: String) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+impl Pid1Observer for Recorder {
+    fn signal_received(&self, signal: c_int) {
+        self.record(format!("received {signal}"));
+    }
+
+    fn signal_forwarded(&self, signal: c_int, _pid: i32) {
+        self.record(format!("forwarded {signal}"));
+    }
+
+    fn process_reaped(&self, process: &ReapedProcess) {
+        self.record(format!(
+            "reaped {:?} orphan={}",
+            process.exit_code(),
+            process.orphan
+        ));
+    }
+
+    fn main_exited(&self, status: &ChildStatus) {
+        self.record(format!("exited {}", status.exit_code()));
+    }
+}
+
+#[test]
+fn observer_sees_lifecycle_events() {
+    let recorder = Arc::new(Recorder::default());
+    let mut settings = Pid1Settings::new();
+    settings.observer(recorder.clone());
+
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(
+        signals,
+        Command::new("sh").args([
+            "-c",
+            "trap 'exit 4' HUP; kill -HUP $PPID; while true; do sleep 0.05; done",
+        ]),
+    );
+    assert_eq!(outcome.exit_code(), 4);
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            format!("received {SIGHUP}"),
+            format!("forwarded {SIGHUP}"),
+            "reaped Some(4) orphan=false".to_owned(),
+            "exited 4".to_owned(),
+        ]
+    );
+}
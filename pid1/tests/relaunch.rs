@@ -0,0 +1,103 @@
+#![cfg(target_os = "linux")]
+
+use pid1::{Pid1Settings, RelaunchStrategy};
+use std::{
+    fs::Permissions,
+    os::unix::{fs::PermissionsExt, process::CommandExt},
+    path::Path,
+    process::{Command, Output},
+};
+
+/// Makes [`launched`] call [`Pid1Settings::launch`] with the given
+/// strategy, `path`, `fd` or `env_clear`, rather than do nothing.
+const LAUNCH: &str = "PID1_TEST_LAUNCH";
+
+/// Run the test binary at `exe` as `argv0`, running [`launched`] only.
+fn launch(exe: &Path, argv0: &str, strategy: &str) -> Output {
+    run(Command::new(exe).arg0(argv0).env(LAUNCH, strategy))
+}
+
+fn run(command: &mut Command) -> Output {
+    let output = command
+        .args(["launched", "--exact", "--nocapture"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    output
+}
+
+#[test]
+fn launched() {
+    let Some(strategy) = std::env::var_os(LAUNCH) else {
+        return;
+    };
+    let mut settings = Pid1Settings::new();
+    settings.subreaper(true);
+    if strategy == "fd" && std::env::var_os("PID1_RS_RELAUNCHED").is_none() {
+        // Only in the original process, not in the relaunched child:
+        // the child can only be run through the open executable
+        std::fs::remove_file(std::env::current_exe().unwrap()).unwrap();
+        settings.relaunch_strategy(RelaunchStrategy::FileDescriptor);
+    }
+    if strategy == "env_clear" {
+        settings.configure_relaunch(|command| {
+            command.env_clear().env(LAUNCH, "env_clear");
+        });
+    }
+    settings.launch().unwrap();
+    // Only the relaunched child gets here
+    let argv0 = std::env::args().next().unwrap();
+    println!("argv[0]: {argv0}");
+}
+
+#[test]
+fn relaunch_keeps_argv0() {
+    let exe = std::env::current_exe().unwrap();
+    let output = launch(&exe, "pid1-test", "path");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("argv[0]: pid1-test\n"), "{stdout}");
+}
+
+#[test]
+fn relaunch_through_file_descriptor() {
+    let exe = std::env::temp_dir().join(format!("pid1-relaunch-{}", std::process::id()));
+    std::fs::copy(std::env::current_exe().unwrap(), &exe).unwrap();
+    let output = launch(&exe, "pid1-test", "fd");
+    assert!(!exe.exists());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("argv[0]: pid1-test\n"), "{stdout}");
+}
+
+#[test]
+fn relaunch_with_cleared_environment() {
+    let exe = std::env::current_exe().unwrap();
+    let output = launch(&exe, "pid1-test", "env_clear");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.matches("argv[0]: pid1-test\n").count(),
+        1,
+        "{stdout}"
+    );
+}
+
+#[test]
+fn relaunch_execute_only_file() {
+    // Neither the binary nor its directory are readable, and the binary
+    // is deleted, by a user other than root when possible
+    let dir = std::env::temp_dir().join(format!("pid1-relaunch-dir-{}", std::process::id()));
+    std::fs::create_dir(&dir).unwrap();
+    let exe = dir.join("pid1-test");
+    std::fs::copy(std::env::current_exe().unwrap(), &exe).unwrap();
+    std::fs::set_permissions(&exe, Permissions::from_mode(0o111)).unwrap();
+    std::fs::set_permissions(&dir, Permissions::from_mode(0o733)).unwrap();
+    let mut command = Command::new(&exe);
+    command.arg0("pid1-test").env(LAUNCH, "fd");
+    if unsafe { libc::geteuid() } == 0 {
+        command.uid(65534).gid(65534);
+    }
+    let output = run(&mut command);
+    assert!(!exe.exists());
+    std::fs::remove_dir(&dir).unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("argv[0]: pid1-test\n"), "{stdout}");
+}
@@ -0,0 +1,77 @@
+#![cfg(target_family = "unix")]
+
+mod common;
+
+use common::sh;
+use pid1::{ChildStatus, Pid1Settings, Restart, RestartPolicy};
+use std::time::Duration;
+
+#[test]
+fn parse_restart() {
+    assert_eq!("never".parse(), Ok(Restart::Never));
+    assert_eq!("on-failure".parse(), Ok(Restart::OnFailure));
+    assert_eq!("Always".parse(), Ok(Restart::Always));
+    assert!("sometimes".parse::<Restart>().is_err());
+}
+
+#[test]
+fn restart_child() {
+    let delay = Duration::from_millis(10);
+    let mut settings = Pid1Settings::new();
+
+    // Gives up on a crash loop with the status of the last failure
+    settings.restart_policy(
+        RestartPolicy::new(Restart::OnFailure)
+            .backoff(delay, delay)
+            .max_restarts(2, Duration::from_secs(60)),
+    );
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh("exit 3"));
+    assert!(matches!(outcome.status, ChildStatus::Exited(3)));
+    assert_eq!(outcome.restarts, 2);
+
+    // Stops restarting once the child succeeds
+    let marker = std::env::temp_dir().join(format!("pid1-restart-{}", std::process::id()));
+    let script = format!(
+        "if [ -e {0} ]; then exit 0; else touch {0}; exit 7; fi",
+        marker.display()
+    );
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh(&script));
+    let _ = std::fs::remove_file(&marker);
+    assert!(matches!(outcome.status, ChildStatus::Exited(0)));
+    assert_eq!(outcome.restarts, 1);
+
+    // Reports the last failure rather than a successful exit
+    settings.restart_policy(
+        RestartPolicy::new(Restart::Always)
+            .backoff(delay, delay)
+            .max_restarts(1, Duration::from_secs(60)),
+    );
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh(&script));
+    let _ = std::fs::remove_file(&marker);
+    assert!(matches!(outcome.status, ChildStatus::Exited(7)));
+    assert_eq!(outcome.restarts, 1);
+
+    // The processes left behind by the restarted child are terminated
+    #[cfg(target_os = "linux")]
+    {
+        use pid1::MainExitPolicy;
+
+        pid1::become_child_subreaper().unwrap();
+        let script = format!(
+            "if [ -e {0} ]; then (sleep 10 &); exit 0; else touch {0}; exit 7; fi",
+            marker.display()
+        );
+        settings
+            .restart_policy(RestartPolicy::new(Restart::OnFailure).backoff(delay, delay))
+            .on_main_exit(MainExitPolicy::TerminateDescendants(Duration::from_secs(2)));
+        let signals = settings.register_signals().unwrap();
+        let outcome = settings.supervise_command(signals, &mut sh(&script));
+        let _ = std::fs::remove_file(&marker);
+        assert!(matches!(outcome.status, ChildStatus::Exited(0)));
+        assert_eq!(outcome.restarts, 1);
+        assert_eq!(outcome.orphans_reaped, 1);
+    }
+}
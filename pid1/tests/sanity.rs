@@ -58,7 +58,7 @@ fn sanity_test() {
     assert!(output.status.success(), "Process exited successfully");
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(
-        stdout.contains(&"pid1-rs: Process running as PID 1"),
+        stdout.contains("pid1-rs: Process running as PID 1"),
         "One process runs as pid1",
     );
 }
@@ -98,7 +98,7 @@ fn reaps_zombie_process() {
     assert!(output.status.success(), "Process exited successfully");
 
     assert!(
-        stdout.contains(&"pid1-rs: Reaped PID"),
+        stdout.contains("pid1-rs: Reaped PID"),
         "Successfully Reaped process",
     );
 
@@ -0,0 +1,50 @@
+#![cfg(target_family = "unix")]
+
+mod common;
+
+use common::sh;
+use pid1::{ChildStatus, Pid1Settings, Service};
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn supervise_services() {
+    let settings = Pid1Settings::new();
+
+    // A critical service exiting stops the other ones
+    let start = Instant::now();
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_services(
+        signals,
+        &mut [
+            Service::new("app", sh("exit 3")),
+            Service::new("helper", sh("exec sleep 10")).critical(false),
+        ],
+    );
+    assert!(matches!(outcome.status, ChildStatus::Exited(3)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // Other services keep running when an optional one exits
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_services(
+        signals,
+        &mut [
+            Service::new("app", sh("sleep 0.3; exit 4")),
+            Service::new("helper", sh("exit 5")).critical(false),
+        ],
+    );
+    assert!(matches!(outcome.status, ChildStatus::Exited(4)));
+
+    // Failing to spawn a critical service stops the other ones
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_services(
+        signals,
+        &mut [
+            Service::new("helper", sh("exec sleep 10")).critical(false),
+            Service::new("app", Command::new("/does/not/exist")),
+        ],
+    );
+    assert!(matches!(outcome.status, ChildStatus::SpawnFailed(_)));
+}
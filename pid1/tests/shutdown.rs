@@ -0,0 +1,98 @@
+#![cfg(target_family = "unix")]
+
+mod common;
+
+use common::sh;
+use pid1::{
+    parse_duration, ChildStatus, MainExitPolicy, Pid1Observer, Pid1Settings, ShutdownPolicy,
+    ShutdownStep,
+};
+use signal_hook::consts::{SIGINT, SIGKILL, SIGTERM};
+use std::{
+    ffi::c_int,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Records the steps of the shutdown taken.
+#[derive(Default)]
+struct Steps(Mutex<Vec<(usize, c_int)>>);
+
+impl Pid1Observer for Steps {
+    fn shutdown_step(&self, step: usize, signal: c_int, _pid: i32) {
+        self.0.lock().unwrap().push((step, signal));
+    }
+}
+
+#[test]
+fn parses_durations() {
+    assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+    assert_eq!(parse_duration("10s"), Some(Duration::from_secs(10)));
+    assert_eq!(parse_duration("7"), Some(Duration::from_secs(7)));
+    assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+    assert_eq!(parse_duration("10x"), None);
+    assert_eq!(parse_duration("s"), None);
+    assert_eq!(parse_duration(&format!("{}h", u64::MAX / 60)), None);
+}
+
+#[test]
+fn parses_stop_sequence() {
+    let policy: ShutdownPolicy = "TERM:10s,INT:5s,KILL".parse().unwrap();
+    assert_eq!(
+        policy,
+        ShutdownPolicy::new()
+            .step(SIGTERM, Duration::from_secs(10))
+            .step(SIGINT, Duration::from_secs(5))
+            .step(SIGKILL, Duration::ZERO)
+    );
+
+    let policy: ShutdownPolicy = "received:3s,KILL".parse().unwrap();
+    assert_eq!(
+        policy.steps()[0],
+        ShutdownStep {
+            signal: None,
+            wait: Duration::from_secs(3)
+        }
+    );
+}
+
+#[test]
+fn rejects_invalid_stop_sequence() {
+    assert!("TERM:10x".parse::<ShutdownPolicy>().is_err());
+    assert!("NOPE:1s".parse::<ShutdownPolicy>().is_err());
+    assert!("".parse::<ShutdownPolicy>().is_err());
+}
+
+#[test]
+fn parses_main_exit_policy() {
+    assert_eq!("exit".parse(), Ok(MainExitPolicy::Exit));
+    assert_eq!(
+        "terminate:5s".parse(),
+        Ok(MainExitPolicy::TerminateDescendants(Duration::from_secs(5)))
+    );
+    assert_eq!(
+        "terminate".parse(),
+        Ok(MainExitPolicy::TerminateDescendants(Duration::from_secs(2)))
+    );
+    assert!("exit:5s".parse::<MainExitPolicy>().is_err());
+}
+
+#[test]
+fn stop_sequence_ends_once_the_child_exits() {
+    let steps = Arc::new(Steps::default());
+    let mut settings = Pid1Settings::new();
+    settings
+        .shutdown_policy("TERM:5s,KILL".parse().unwrap())
+        .observer(steps.clone());
+
+    let signals = settings.register_signals().unwrap();
+    let start = Instant::now();
+    let outcome = settings.supervise_command(
+        signals,
+        &mut sh("trap 'exit 3' TERM; kill -TERM $PPID; while true; do sleep 0.05; done"),
+    );
+    assert!(matches!(outcome.status, ChildStatus::Exited(3)));
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(*steps.0.lock().unwrap(), [(1, SIGTERM)]);
+}
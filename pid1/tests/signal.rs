@@ -0,0 +1,36 @@
+#![cfg(target_family = "unix")]
+
+use pid1::{catchable_signals, parse_signal};
+use signal_hook::consts::{SIGCHLD, SIGCONT, SIGHUP, SIGKILL, SIGTERM, SIGTSTP, SIGUSR1};
+
+#[test]
+fn parses_signal_names_and_numbers() {
+    assert_eq!(parse_signal("TERM"), Some(SIGTERM));
+    assert_eq!(parse_signal("sigterm"), Some(SIGTERM));
+    assert_eq!(parse_signal("SIGUSR1"), Some(SIGUSR1));
+    assert_eq!(parse_signal("1"), Some(SIGHUP));
+    assert_eq!(parse_signal("NOTASIGNAL"), None);
+    assert_eq!(parse_signal("0"), None);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn parses_realtime_signals() {
+    use pid1::signal_name;
+
+    let rtmin = parse_signal("RTMIN").unwrap();
+    assert_eq!(parse_signal("SIGRTMIN+3"), Some(rtmin + 3));
+    assert_eq!(signal_name(rtmin + 3), "SIGRTMIN+3");
+    assert_eq!(parse_signal("RTMAX+1"), None);
+}
+
+#[test]
+fn catchable_signals_exclude_reserved_ones() {
+    let signals = catchable_signals();
+    assert!(signals.contains(&SIGTERM));
+    assert!(signals.contains(&SIGHUP));
+    assert!(signals.contains(&SIGTSTP));
+    assert!(signals.contains(&SIGCONT));
+    assert!(!signals.contains(&SIGKILL));
+    assert!(!signals.contains(&SIGCHLD));
+}
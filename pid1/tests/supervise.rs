@@ -0,0 +1,144 @@
+#![cfg(target_family = "unix")]
+
+mod common;
+
+use common::sh;
+use pid1::{ChildStatus, Pid1Settings, ReapedProcess};
+use signal_hook::consts::{SIGQUIT, SIGSEGV, SIGTERM, SIGUSR1};
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn supervise_returns_outcome() {
+    let settings = Pid1Settings::new();
+
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh("exit 3"));
+    assert!(matches!(outcome.status, ChildStatus::Exited(3)));
+    assert_eq!(outcome.exit_code(), 3);
+
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut sh("kill -USR1 $$"));
+    assert!(matches!(
+        outcome.status,
+        ChildStatus::Signaled(SIGUSR1, false)
+    ));
+    assert_eq!(outcome.exit_code(), 128 + SIGUSR1);
+
+    let signals = settings.register_signals().unwrap();
+    let outcome = settings.supervise_command(signals, &mut Command::new("/does/not/exist"));
+    assert!(matches!(outcome.status, ChildStatus::SpawnFailed(_)));
+
+    // The final exit code is translated, the status is left as is
+    let mut mapped = settings.clone();
+    mapped
+        .success_exit_codes([128 + SIGUSR1])
+        .map_exit_code(3, 4);
+    let signals = mapped.register_signals().unwrap();
+    let outcome = mapped.supervise_command(signals, &mut sh("kill -USR1 $$"));
+    assert!(matches!(
+        outcome.status,
+        ChildStatus::Signaled(SIGUSR1, false)
+    ));
+    assert_eq!(outcome.exit_code(), 0);
+    let signals = mapped.register_signals().unwrap();
+    let outcome = mapped.supervise_command(signals, &mut sh("exit 3"));
+    assert_eq!(outcome.exit_code(), 4);
+
+    // A stopped child is continued so that it acts on SIGTERM
+    let script = format!(
+        "(sleep 0.2; kill -TERM {}) & kill -STOP $$; exec sleep 10",
+        std::process::id()
+    );
+    let signals = settings.register_signals().unwrap();
+    let start = Instant::now();
+    let outcome = settings.supervise_command(signals, &mut sh(&script));
+    assert!(matches!(outcome.status, ChildStatus::Signaled(SIGTERM, _)));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    // Signals are rewritten before they are forwarded, or dropped
+    let mut rewritten = settings.clone();
+    rewritten
+        .rewrite_signal(SIGTERM, Some(SIGQUIT))
+        .rewrite_signal(SIGUSR1, None);
+    let signals = rewritten.register_signals().unwrap();
+    let outcome = rewritten.supervise_command(
+        signals,
+        &mut sh("trap 'exit 5' QUIT; kill -TERM $PPID; while true; do sleep 0.05; done"),
+    );
+    assert!(matches!(outcome.status, ChildStatus::Exited(5)));
+    let signals = rewritten.register_signals().unwrap();
+    let outcome = rewritten.supervise_command(
+        signals,
+        &mut sh("trap 'exit 6' USR1; trap 'exit 7' USR2; \
+                 kill -USR1 $PPID; sleep 0.2; kill -USR2 $PPID; \
+                 while true; do sleep 0.05; done"),
+    );
+    assert!(matches!(outcome.status, ChildStatus::Exited(7)));
+
+    // In process group mode, the whole pipeline gets the signals
+    let mut group = settings.clone();
+    group.process_group(true);
+    let signals = group.register_signals().unwrap();
+    let start = Instant::now();
+    let outcome = group.supervise_command(
+        signals,
+        &mut sh("trap 'exit 3' TERM; (sleep 0.2; kill -TERM $PPID) & sleep 10 | sleep 10"),
+    );
+    assert!(matches!(outcome.status, ChildStatus::Exited(3)));
+    assert!(start.elapsed() < Duration::from_secs(2));
+    // A child without its own process group gets them alone
+    let signals = group.register_signals().unwrap();
+    let child = sh("trap 'exit 3' TERM; kill -TERM $PPID; while true; do sleep 0.05; done")
+        .spawn()
+        .unwrap();
+    let outcome = group.supervise(signals, child);
+    assert!(matches!(outcome.status, ChildStatus::Exited(3)));
+
+    // In TTY mode, the child gets its own process group even when
+    // stdin is not a terminal
+    #[cfg(target_os = "linux")]
+    {
+        let mut tty = settings.clone();
+        tty.tty(true);
+        let signals = tty.register_signals().unwrap();
+        let outcome = tty.supervise_command(
+            signals,
+            sh("set -- $(cat /proc/$$/stat); [ \"$5\" = $$ ]").stdin(std::process::Stdio::null()),
+        );
+        assert!(matches!(outcome.status, ChildStatus::Exited(0)));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        pid1::become_child_subreaper().unwrap();
+        let signals = settings.register_signals().unwrap();
+        let outcome = settings.supervise_command(signals, &mut sh("(sleep 0.1 &); sleep 0.5"));
+        assert!(matches!(outcome.status, ChildStatus::Exited(0)));
+        assert_eq!(outcome.orphans_reaped, 1);
+    }
+}
+
+#[test]
+fn reaped_process_display() {
+    let process = ReapedProcess {
+        pid: 42,
+        status: ChildStatus::Signaled(SIGSEGV, true),
+        orphan: false,
+    };
+    assert_eq!(process.signal(), Some(SIGSEGV));
+    assert!(process.core_dumped());
+    assert_eq!(
+        process.to_string(),
+        "PID 42 was killed by SIGSEGV (core dumped)"
+    );
+    let process = ReapedProcess {
+        pid: 43,
+        status: ChildStatus::Exited(0),
+        orphan: true,
+    };
+    assert_eq!(process.exit_code(), Some(0));
+    assert_eq!(process.to_string(), "PID 43 (orphan) exited with code 0");
+}
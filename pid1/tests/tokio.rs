@@ -0,0 +1,69 @@
+#![cfg(all(target_family = "unix", feature = "tokio"))]
+
+use pid1::{
+    tokio::Supervisor, ChildStatus, HealthCheck, HealthProbe, Pid1Settings, UnhealthyAction,
+};
+use signal_hook::consts::{SIGTERM, SIGUSR1};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+#[tokio::test]
+async fn supervisor_returns_outcome() {
+    let settings = Pid1Settings::new();
+
+    let supervisor =
+        Supervisor::spawn(&settings, Command::new("sh").args(["-c", "exit 3"])).unwrap();
+    let outcome = supervisor.wait().await.unwrap();
+    assert!(matches!(outcome.status, ChildStatus::Exited(3)));
+
+    // The signal sent to PID 1 is forwarded to the child
+    let supervisor = Supervisor::spawn(
+        &settings,
+        Command::new("sh").args([
+            "-c",
+            "trap 'exit 4' USR1; kill -USR1 $PPID; while true; do sleep 0.05; done",
+        ]),
+    )
+    .unwrap();
+    let outcome = supervisor.wait().await.unwrap();
+    assert!(matches!(outcome.status, ChildStatus::Exited(4)));
+
+    let supervisor =
+        Supervisor::spawn(&settings, Command::new("sh").args(["-c", "kill -USR1 $$"])).unwrap();
+    let outcome = supervisor.wait().await.unwrap();
+    assert!(matches!(
+        outcome.status,
+        ChildStatus::Signaled(SIGUSR1, false)
+    ));
+
+    // The health check starts without waiting for a signal
+    let probe = HealthProbe::Exec {
+        program: "false".to_owned(),
+        args: Vec::new(),
+    };
+    let mut unhealthy = settings.clone();
+    unhealthy.health_check(
+        HealthCheck::new(probe)
+            .interval(Duration::from_millis(50))
+            .failure_threshold(1)
+            .action(UnhealthyAction::Shutdown),
+    );
+    let start = Instant::now();
+    let supervisor = Supervisor::spawn(&unhealthy, Command::new("sleep").arg("3")).unwrap();
+    let outcome = supervisor.wait().await.unwrap();
+    assert!(matches!(outcome.status, ChildStatus::Signaled(SIGTERM, _)));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    #[cfg(target_os = "linux")]
+    {
+        pid1::become_child_subreaper().unwrap();
+        let supervisor = Supervisor::spawn(
+            &settings,
+            Command::new("sh").args(["-c", "(sleep 0.1 &); sleep 0.5"]),
+        )
+        .unwrap();
+        let outcome = supervisor.wait().await.unwrap();
+        assert!(matches!(outcome.status, ChildStatus::Exited(0)));
+        assert_eq!(outcome.orphans_reaped, 1);
+    }
+}